@@ -0,0 +1,181 @@
+use crate::refcell::{BorrowError, BorrowMutError};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// `state` holds the number of shared borrows, or `EXCLUSIVE` while mutably borrowed.
+const EXCLUSIVE: usize = usize::MAX;
+
+/// A `RefCell` that can be shared between threads, tracking borrows with an atomic counter.
+///
+/// Borrows are still checked at runtime and never block: a conflicting borrow fails
+/// right away, as it does for `RefCell`.
+pub struct AtomicRefCell<T> {
+    value: UnsafeCell<T>,
+    state: AtomicUsize,
+}
+
+// SAFETY: Shared borrows hand out `&T` to several threads at once, which needs `T: Sync`,
+// and a mutable borrow lets any thread take the value out of a `&mut T`, which needs
+// `T: Send`. The state is only changed atomically, so no two threads ever get conflicting
+// borrows.
+unsafe impl<T: Send + Sync> Sync for AtomicRefCell<T> {}
+
+impl<T> AtomicRefCell<T> {
+    pub fn new(value: T) -> Self {
+        AtomicRefCell {
+            value: UnsafeCell::new(value),
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    /// Attempts to borrow the value, failing if it is mutably borrowed.
+    pub fn try_borrow(&self) -> Result<AtomicRef<'_, T>, BorrowError> {
+        let mut current = self.state.load(Ordering::Relaxed);
+        loop {
+            if current == EXCLUSIVE {
+                return Err(BorrowError);
+            }
+            // One below `EXCLUSIVE`, so the count never turns into the exclusive marker.
+            assert!(current < EXCLUSIVE - 1, "too many shared borrows");
+            // Acquire pairs with the Release in `AtomicRefMut::drop`, so the writes made
+            // through the last mutable borrow are visible.
+            match self.state.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(AtomicRef { cell: self }),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Attempts to borrow the value mutably, failing if it is borrowed at all.
+    pub fn try_borrow_mut(&self) -> Result<AtomicRefMut<'_, T>, BorrowMutError> {
+        // Only an unborrowed cell can be claimed, so a failed exchange is a real conflict
+        // rather than a retry: a strong exchange doesn't fail spuriously.
+        self.state
+            .compare_exchange(0, EXCLUSIVE, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| AtomicRefMut { cell: self })
+            .map_err(|_| BorrowMutError)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: Default> Default for AtomicRefCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// A shared borrow of the value inside an `AtomicRefCell`.
+pub struct AtomicRef<'cell, T> {
+    cell: &'cell AtomicRefCell<T>,
+}
+
+impl<T> std::ops::Deref for AtomicRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The state counts this borrow, so no mutable borrow exists until it is dropped.
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for AtomicRef<'_, T> {
+    fn drop(&mut self) {
+        self.cell.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// A mutable borrow of the value inside an `AtomicRefCell`.
+pub struct AtomicRefMut<'cell, T> {
+    cell: &'cell AtomicRefCell<T>,
+}
+
+impl<T> std::ops::Deref for AtomicRefMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: see safety for DerefMut
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl<T> std::ops::DerefMut for AtomicRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The state is `EXCLUSIVE` while this borrow exists, so no other borrow
+        // of the value can be taken on any thread.
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl<T> Drop for AtomicRefMut<'_, T> {
+    fn drop(&mut self) {
+        // Release publishes the writes made through this borrow to the next borrower.
+        self.cell.state.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_borrow_rules() {
+        let cell = AtomicRefCell::new(5);
+        let first = cell.try_borrow().unwrap();
+        let second = cell.try_borrow().unwrap();
+        assert_eq!(*first + *second, 10);
+        assert_eq!(cell.try_borrow_mut().err(), Some(BorrowMutError));
+        drop((first, second));
+
+        let mut modifier = cell.try_borrow_mut().unwrap();
+        *modifier += 1;
+        assert_eq!(cell.try_borrow().err(), Some(BorrowError));
+        assert_eq!(cell.try_borrow_mut().err(), Some(BorrowMutError));
+        drop(modifier);
+
+        assert_eq!(*cell.try_borrow().unwrap(), 6);
+        assert_eq!(cell.into_inner(), 6);
+    }
+
+    #[test]
+    fn test_contended_borrows_never_conflict() {
+        let cell = AtomicRefCell::new(0usize);
+        // Tracked next to the cell, so a borrow that shouldn't have been granted shows up.
+        let readers = AtomicUsize::new(0);
+        let writers = AtomicUsize::new(0);
+        let writes = AtomicUsize::new(0);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for i in 0..2_000 {
+                        if i % 3 == 0 {
+                            if let Ok(mut value) = cell.try_borrow_mut() {
+                                assert_eq!(writers.fetch_add(1, Ordering::SeqCst), 0);
+                                assert_eq!(readers.load(Ordering::SeqCst), 0);
+                                *value += 1;
+                                writes.fetch_add(1, Ordering::Relaxed);
+                                thread::yield_now();
+                                writers.fetch_sub(1, Ordering::SeqCst);
+                            }
+                        } else if let Ok(value) = cell.try_borrow() {
+                            readers.fetch_add(1, Ordering::SeqCst);
+                            assert_eq!(writers.load(Ordering::SeqCst), 0);
+                            let _ = *value;
+                            thread::yield_now();
+                            readers.fetch_sub(1, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(cell.into_inner(), writes.into_inner());
+    }
+}
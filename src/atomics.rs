@@ -1,12 +1,22 @@
 use std::cell::UnsafeCell;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::Thread;
 
 const LOCKED: bool = true;
 const UNLOCKED: bool = false;
 
+/// Number of spins after which `with_lock_v3` warns about a likely deadlock in debug builds.
+#[cfg(debug_assertions)]
+const SPIN_WARNING_THRESHOLD: u64 = 10_000_000;
+
 pub struct Mutex<T> {
     locked: AtomicBool,
     value: UnsafeCell<T>,
+    /// Threads parked in `with_lock_parking`, woken one at a time on unlock.
+    waiters: std::sync::Mutex<VecDeque<Thread>>,
 }
 
 unsafe impl<T> Sync for Mutex<T> where T: Send {}
@@ -16,6 +26,7 @@ impl<T> Mutex<T> {
         Self {
             locked: AtomicBool::new(UNLOCKED),
             value: UnsafeCell::new(t),
+            waiters: std::sync::Mutex::new(VecDeque::new()),
         }
     }
 
@@ -51,6 +62,8 @@ impl<T> Mutex<T> {
     }
 
     pub fn with_lock_v3<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        #[cfg(debug_assertions)]
+        let mut spins: u64 = 0;
         while self
             .locked
             .compare_exchange_weak(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
@@ -59,6 +72,16 @@ impl<T> Mutex<T> {
             // MESI protocol: stay in Shared state when locked
             while self.locked.load(Ordering::Relaxed) == LOCKED {
                 std::hint::spin_loop();
+                #[cfg(debug_assertions)]
+                {
+                    spins += 1;
+                    if spins == SPIN_WARNING_THRESHOLD {
+                        eprintln!(
+                            "warning: with_lock_v3 spun {spins} times waiting for the lock, \
+                             it is likely never released (deadlock?)"
+                        );
+                    }
+                }
             }
         }
         // SAFETY: this thread holds the lock, therefore we can create a mutable reference
@@ -66,6 +89,325 @@ impl<T> Mutex<T> {
         self.locked.store(UNLOCKED, Ordering::Release);
         ret
     }
+
+    /// Like `with_lock_v3`, but parks the thread instead of spinning while the lock is held.
+    ///
+    /// A waiting thread registers its `Thread` handle and parks; the thread releasing the lock
+    /// unparks the first registered waiter. Only unlocks done by `with_lock_parking` wake
+    /// waiters, so don't mix it with the spinning lock methods on the same `Mutex`.
+    pub fn with_lock_parking<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut registered = false;
+        while self
+            .locked
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            let current = std::thread::current();
+            {
+                let mut waiters = self.waiters.lock().unwrap();
+                if !waiters.iter().any(|waiter| waiter.id() == current.id()) {
+                    waiters.push_back(current);
+                }
+            }
+            registered = true;
+            // The lock may have been released before we registered, in which case nobody
+            // is going to unpark us. Parking can also wake up spuriously, so retry either way.
+            if self.locked.load(Ordering::Relaxed) == LOCKED {
+                std::thread::park();
+            }
+        }
+        if registered {
+            // We may have acquired the lock without being popped, e.g. after a spurious wakeup.
+            // A stale entry would swallow the wakeup meant for a thread that is still waiting.
+            let id = std::thread::current().id();
+            self.waiters
+                .lock()
+                .unwrap()
+                .retain(|waiter| waiter.id() != id);
+        }
+        // SAFETY: this thread holds the lock, therefore we can create a mutable reference
+        let ret = f(unsafe { &mut *self.value.get() });
+        self.locked.store(UNLOCKED, Ordering::Release);
+        if let Some(waiter) = self.waiters.lock().unwrap().pop_front() {
+            waiter.unpark();
+        }
+        ret
+    }
+
+    /// Spins until the lock is acquired, with the same orderings as `with_lock_v3`.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // MESI protocol: stay in Shared state when locked
+            while self.locked.load(Ordering::Relaxed) == LOCKED {
+                std::hint::spin_loop();
+            }
+        }
+        MutexGuard {
+            mutex: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Makes exactly one attempt to acquire the lock, without spinning or yielding.
+    pub fn lock_now(&self) -> Result<MutexGuard<'_, T>, WouldBlock> {
+        // A strong compare_exchange, since a spurious failure would be an unnecessary `Err`.
+        match self
+            .locked
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+        {
+            Ok(_) => Ok(MutexGuard {
+                mutex: self,
+                _marker: PhantomData,
+            }),
+            Err(_) => Err(WouldBlock),
+        }
+    }
+
+    /// Returns whether the lock is currently held.
+    ///
+    /// The result is advisory only: another thread may acquire or release the lock right
+    /// after the `Relaxed` load, so it must not be used to decide whether locking is safe.
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed) == LOCKED
+    }
+}
+
+/// The error returned when the lock is already held by someone else.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WouldBlock;
+
+impl std::fmt::Display for WouldBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the lock is already held")
+    }
+}
+
+impl std::error::Error for WouldBlock {}
+
+/// Gives access to the value of a locked `Mutex` and releases the lock when dropped.
+pub struct MutexGuard<'mutex, T> {
+    mutex: &'mutex Mutex<T>,
+    // Makes the guard Send and Sync only like a `&mut T` would be.
+    _marker: PhantomData<&'mutex mut T>,
+}
+
+impl<'mutex, T> MutexGuard<'mutex, T> {
+    /// Narrows the guard to a part of the value, e.g. a field, keeping the lock held until
+    /// the returned guard is dropped.
+    ///
+    /// An associated function, so it can't shadow a `map` method on `T`.
+    pub fn map<U>(guard: Self, f: impl FnOnce(&mut T) -> &mut U) -> MappedMutexGuard<'mutex, U> {
+        let mutex = guard.mutex;
+        // SAFETY: the guard exists only while this thread holds the lock. If `f` panics,
+        // `guard` is still dropped and releases the lock.
+        let value = NonNull::from(f(unsafe { &mut *mutex.value.get() }));
+        // The mapped guard takes over releasing the lock.
+        std::mem::forget(guard);
+        MappedMutexGuard {
+            locked: &mutex.locked,
+            value,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> std::ops::Deref for MutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the guard exists only while this thread holds the lock.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T> std::ops::DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the guard exists only while this thread holds the lock,
+        // therefore we can create a mutable reference.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.locked.store(UNLOCKED, Ordering::Release);
+    }
+}
+
+/// A `MutexGuard` narrowed to a part of the locked value by `MutexGuard::map`.
+pub struct MappedMutexGuard<'mutex, U> {
+    locked: &'mutex AtomicBool,
+    value: NonNull<U>,
+    _marker: PhantomData<&'mutex mut U>,
+}
+
+impl<U> std::ops::Deref for MappedMutexGuard<'_, U> {
+    type Target = U;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `value` points into the locked value, and the lock is held until this
+        // guard is dropped.
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<U> std::ops::DerefMut for MappedMutexGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: see Deref, the lock gives this thread exclusive access.
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<U> Drop for MappedMutexGuard<'_, U> {
+    fn drop(&mut self) {
+        self.locked.store(UNLOCKED, Ordering::Release);
+    }
+}
+
+/// A counter shared between threads without a lock.
+pub struct AtomicCounter {
+    value: AtomicUsize,
+}
+
+impl AtomicCounter {
+    pub fn new(value: usize) -> Self {
+        Self {
+            value: AtomicUsize::new(value),
+        }
+    }
+
+    /// Returns the current value. Uses `Ordering::Acquire` to see writes published by updates.
+    pub fn get(&self) -> usize {
+        self.value.load(Ordering::Acquire)
+    }
+
+    /// Stores `new` only if the counter still holds `current`.
+    ///
+    /// Returns `Ok(previous)` on success and `Err(actual)` with the value found otherwise.
+    /// A successful exchange uses `Ordering::AcqRel`: it acquires what the previous update
+    /// published and releases everything written before it. A failed exchange only reads,
+    /// so `Ordering::Acquire` is sufficient for it.
+    pub fn compare_and_set(&self, current: usize, new: usize) -> Result<usize, usize> {
+        self.value
+            .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
+    }
+
+    /// Adds one, wrapping on overflow, returning the previous value.
+    ///
+    /// Uses `Ordering::Relaxed`: the increment itself is atomic, so no update is lost, but it
+    /// doesn't synchronize with other memory. Use `increment_with` when it must.
+    pub fn increment(&self) -> usize {
+        self.increment_with(Ordering::Relaxed)
+    }
+
+    /// Adds one, wrapping on overflow, with the given `ordering`, returning the previous value.
+    ///
+    /// `Relaxed` is enough to count. Stronger orderings additionally publish or observe
+    /// the writes made around the increment, at the cost of extra synchronization.
+    pub fn increment_with(&self, ordering: Ordering) -> usize {
+        self.value.fetch_add(1, ordering)
+    }
+
+    /// Adds one unless the counter is at `usize::MAX`, returning the previous value.
+    pub fn increment_saturating(&self) -> usize {
+        self.increment_by(|value| value.saturating_add(1))
+    }
+
+    /// Adds one, wrapping from `usize::MAX` to 0, returning the previous value.
+    pub fn increment_wrapping(&self) -> usize {
+        self.increment_by(|value| value.wrapping_add(1))
+    }
+
+    /// Sets the counter to the maximum of its value and `value`, returning the previous value.
+    ///
+    /// Uses `Ordering::AcqRel`, like the other updates, so it can track a high-water mark of
+    /// data published by other threads.
+    pub fn fetch_max(&self, value: usize) -> usize {
+        self.value.fetch_max(value, Ordering::AcqRel)
+    }
+
+    /// Sets the counter to the minimum of its value and `value`, returning the previous value.
+    ///
+    /// Uses `Ordering::AcqRel`, see `fetch_max`.
+    pub fn fetch_min(&self, value: usize) -> usize {
+        self.value.fetch_min(value, Ordering::AcqRel)
+    }
+
+    fn increment_by(&self, f: impl Fn(usize) -> usize) -> usize {
+        // `fetch_update` is a CAS loop: it retries `f` until no other thread changed the value
+        // in between. The closure never returns `None`, so the update always succeeds.
+        match self
+            .value
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| Some(f(value)))
+        {
+            Ok(previous) | Err(previous) => previous,
+        }
+    }
+}
+
+/// Outcome distribution collected by `run_ordering_experiment`.
+///
+/// `count(z)` is the number of runs in which `z` of the two readers saw both flags set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observations {
+    counts: [usize; 3],
+}
+
+impl Observations {
+    /// Returns how many runs ended with the given `z` (0, 1 or 2).
+    pub fn count(&self, z: usize) -> usize {
+        self.counts.get(z).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of runs observed.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+/// Runs the two-writers/two-readers scenario `iterations` times and records how often each
+/// outcome occurs.
+///
+/// Two threads store `true` into `x` and `y`. One reader waits for `x` and then checks `y`,
+/// the other waits for `y` and then checks `x`; each reader that sees the other flag set
+/// increments `z`. Stores use `ordering` and loads use its load counterpart, so `Acquire`,
+/// `Release` and `AcqRel` all run as release stores paired with acquire loads.
+///
+/// With `SeqCst` all threads agree on a single order of the two stores, so `z == 0` can't
+/// happen. With any weaker ordering the readers may observe the stores in opposite orders
+/// and `z == 0` is allowed, although it may be rare or absent on strongly ordered hardware
+/// such as x86.
+pub fn run_ordering_experiment(ordering: Ordering, iterations: usize) -> Observations {
+    let (store, load) = match ordering {
+        Ordering::Relaxed => (Ordering::Relaxed, Ordering::Relaxed),
+        Ordering::SeqCst => (Ordering::SeqCst, Ordering::SeqCst),
+        _ => (Ordering::Release, Ordering::Acquire),
+    };
+    let mut observations = Observations::default();
+    for _ in 0..iterations {
+        let x = AtomicBool::new(false);
+        let y = AtomicBool::new(false);
+        let z = AtomicUsize::new(0);
+        let read = |first: &AtomicBool, second: &AtomicBool| {
+            while !first.load(load) {
+                // Give the writers a chance to run on machines with few cores.
+                std::thread::yield_now();
+            }
+            if second.load(load) {
+                z.fetch_add(1, Ordering::Relaxed);
+            }
+        };
+        std::thread::scope(|s| {
+            s.spawn(|| x.store(true, store));
+            s.spawn(|| y.store(true, store));
+            s.spawn(|| read(&x, &y));
+            s.spawn(|| read(&y, &x));
+        });
+        observations.counts[z.into_inner()] += 1;
+    }
+    observations
 }
 
 #[cfg(test)]
@@ -119,7 +461,6 @@ mod tests {
 
     #[test]
     fn test_relaxed_ordering_example() {
-        use std::sync::atomic::AtomicUsize;
         let x: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
         let y: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
         let thread_1 = spawn(move || {
@@ -170,36 +511,36 @@ mod tests {
     }
 
     #[test]
-    fn test_acquire_release_ordering_example() {
-        use std::sync::atomic::AtomicUsize;
-        let x: &'static AtomicBool = Box::leak(Box::new(AtomicBool::new(false)));
-        let y: &'static AtomicBool = Box::leak(Box::new(AtomicBool::new(false)));
-        let z: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
-
-        let _write_x = spawn(move || x.store(true, Ordering::Release));
-        let _write_y = spawn(move || y.store(true, Ordering::Release));
-        let read_x_then_y = spawn(move || {
-            while !x.load(Ordering::Acquire) {}
-            if y.load(Ordering::Acquire) {
-                z.fetch_add(1, Ordering::Relaxed);
-            }
-        });
-        let read_y_then_x = spawn(move || {
-            while !y.load(Ordering::Acquire) {}
-            if x.load(Ordering::Acquire) {
-                z.fetch_add(1, Ordering::Relaxed);
-            }
+    #[ignore = "spins until the debug-only deadlock warning is printed"]
+    fn test_with_lock_v3_warns_when_lock_is_held_for_too_long() {
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(0)));
+        let holding: &'static _ = Box::leak(Box::new(AtomicBool::new(false)));
+        let holder = spawn(move || {
+            l.with_lock_v3(|_| {
+                holding.store(true, Ordering::Release);
+                // Hold the lock long enough for the waiter to cross the warning threshold.
+                std::thread::sleep(std::time::Duration::from_secs(5));
+            })
         });
-        read_x_then_y.join().unwrap();
-        read_y_then_x.join().unwrap();
-        let z = z.load(Ordering::SeqCst);
+        while !holding.load(Ordering::Acquire) {
+            std::hint::spin_loop();
+        }
+        // Run with `--nocapture` to see the warning on stderr.
+        l.with_lock_v3(|v| *v += 1);
+        holder.join().unwrap();
+        assert_eq!(l.with_lock_v3(|v| *v), 1);
+    }
+
+    #[test]
+    fn test_acquire_release_ordering_example() {
+        let observations = run_ordering_experiment(Ordering::AcqRel, 200);
+        assert_eq!(observations.total(), 200);
         // Possible values for z:
         // z == 2: write_x -> write_y -> read_x_then_y -> read_y_then_x
         // z == 1: write_x -> read_x_then_y -> write_y -> read_y_then_x
-        assert_ne!(z, 0)
         // z == 0:
         // This example demonstrates a situation where sequential ordering is necessary.
-        // Any other ordering may trigger the assert because it would be possible for
+        // Any other ordering may produce z == 0 because it would be possible for
         // the threads read_x_then_y and read_y_then_x to observe changes to the atomics x and y
         // in opposite order.
         // Total sequential ordering requires a full memory fence CPU instruction
@@ -209,29 +550,365 @@ mod tests {
 
     #[test]
     fn test_seqcst_ordering_example() {
-        use std::sync::atomic::AtomicUsize;
-        let x: &'static AtomicBool = Box::leak(Box::new(AtomicBool::new(false)));
-        let y: &'static AtomicBool = Box::leak(Box::new(AtomicBool::new(false)));
-        let z: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
-
-        let _write_x = spawn(move || x.store(true, Ordering::SeqCst));
-        let _write_y = spawn(move || y.store(true, Ordering::SeqCst));
-        let read_x_then_y = spawn(move || {
-            while !x.load(Ordering::SeqCst) {}
-            if y.load(Ordering::SeqCst) {
-                z.fetch_add(1, Ordering::Relaxed);
-            }
+        let observations = run_ordering_experiment(Ordering::SeqCst, 1000);
+        assert_eq!(observations.total(), 1000);
+        // z == 0 is no longer possible.
+        assert_eq!(observations.count(0), 0);
+    }
+
+    #[test]
+    fn test_run_ordering_experiment_accepts_every_ordering() {
+        for ordering in [
+            Ordering::Relaxed,
+            Ordering::Release,
+            Ordering::Acquire,
+            Ordering::AcqRel,
+            Ordering::SeqCst,
+        ] {
+            let observations = run_ordering_experiment(ordering, 10);
+            assert_eq!(observations.total(), 10);
+            assert_eq!(observations.count(3), 0);
+        }
+    }
+
+    /// CPU time consumed so far by the calling thread, where the platform exposes it.
+    fn thread_cpu_time() -> Option<std::time::Duration> {
+        let schedstat = std::fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+        let nanos = schedstat.split_whitespace().next()?.parse().ok()?;
+        Some(std::time::Duration::from_nanos(nanos))
+    }
+
+    #[test]
+    fn test_with_lock_parking() {
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(0)));
+        let handles: Vec<_> = (0..100)
+            .map(|_| {
+                spawn(move || {
+                    for _ in 0..100 {
+                        l.with_lock_parking(|v| {
+                            *v += 1;
+                        })
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(l.with_lock_parking(|v| *v), 100 * 100)
+    }
+
+    #[test]
+    fn test_with_lock_parking_does_not_spin_during_long_critical_section() {
+        use std::time::{Duration, Instant};
+
+        const CRITICAL_SECTION: Duration = Duration::from_millis(200);
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(Vec::new())));
+        let start = Instant::now();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                spawn(move || {
+                    let cpu_before = thread_cpu_time();
+                    l.with_lock_parking(|v| {
+                        v.push(i);
+                        std::thread::sleep(CRITICAL_SECTION);
+                    });
+                    cpu_before.zip(thread_cpu_time()).map(|(b, a)| a - b)
+                })
+            })
+            .collect();
+        let cpu_times: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // The critical sections never overlapped.
+        assert!(start.elapsed() >= 8 * CRITICAL_SECTION);
+        let mut seen = l.with_lock_parking(|v| v.clone());
+        seen.sort();
+        assert_eq!(seen, (0..8).collect::<Vec<_>>());
+        // A spinning waiter would burn CPU for most of the time it waited, up to 1.4s.
+        for cpu_time in cpu_times.into_iter().flatten() {
+            assert!(cpu_time < Duration::from_millis(100), "{cpu_time:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_locked() {
+        let l = Mutex::new(37);
+        assert!(!l.is_locked());
+        let guard = l.lock();
+        assert!(l.is_locked());
+        drop(guard);
+        assert!(!l.is_locked());
+    }
+
+    #[test]
+    fn test_mutex_guard_map() {
+        struct Stats {
+            name: &'static str,
+            hits: u32,
+        }
+
+        let l = Mutex::new(Stats {
+            name: "stats",
+            hits: 0,
         });
-        let read_y_then_x = spawn(move || {
-            while !y.load(Ordering::SeqCst) {}
-            if x.load(Ordering::SeqCst) {
-                z.fetch_add(1, Ordering::Relaxed);
+        let mut hits = MutexGuard::map(l.lock(), |stats| &mut stats.hits);
+        *hits += 37;
+        assert!(l.is_locked());
+        assert!(l.lock_now().is_err());
+        drop(hits);
+        assert!(!l.is_locked());
+
+        let stats = l.lock();
+        assert_eq!((stats.name, stats.hits), ("stats", 37));
+    }
+
+    #[test]
+    fn test_mutex_guard_map_releases_lock_if_f_panics() {
+        let l = Mutex::new(vec![37]);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            MutexGuard::map(l.lock(), |v| -> &mut i32 { panic!("{}", v.len()) })
+        }));
+        assert!(result.is_err());
+        assert!(!l.is_locked());
+    }
+
+    #[test]
+    fn test_lock_now() {
+        let l = Mutex::new(37);
+        let mut guard = l.lock_now().unwrap();
+        *guard = 73;
+        assert!(matches!(l.lock_now(), Err(WouldBlock)));
+        drop(guard);
+        assert_eq!(*l.lock_now().unwrap(), 73);
+    }
+
+    #[test]
+    fn test_lock_now_fails_immediately_while_held_by_another_thread() {
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(0)));
+        let holding: &'static _ = Box::leak(Box::new(AtomicBool::new(false)));
+        let release: &'static _ = Box::leak(Box::new(AtomicBool::new(false)));
+        let holder = spawn(move || {
+            let _guard = l.lock_now().unwrap();
+            holding.store(true, Ordering::Release);
+            while !release.load(Ordering::Acquire) {
+                std::thread::yield_now();
             }
         });
-        read_x_then_y.join().unwrap();
-        read_y_then_x.join().unwrap();
-        let z = z.load(Ordering::SeqCst);
-        assert_ne!(z, 0)
-        // z == 0 is no longer possible.
+        while !holding.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        assert!(l.lock_now().is_err());
+        release.store(true, Ordering::Release);
+        holder.join().unwrap();
+        assert!(l.lock_now().is_ok());
+    }
+
+    #[test]
+    fn test_lock_with_heap_owning_vec() {
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(Vec::<u64>::new())));
+        let handles: Vec<_> = (0..50)
+            .map(|i| {
+                spawn(move || {
+                    for j in 0..100 {
+                        l.lock().push(i * 100 + j);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut values = l.lock();
+        assert_eq!(values.len(), 50 * 100);
+        values.sort_unstable();
+        assert_eq!(*values, (0..50 * 100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_lock_with_heap_owning_string() {
+        let l: &'static _ = Box::leak(Box::new(Mutex::new(String::new())));
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                spawn(move || {
+                    l.lock().push_str("ab");
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(*l.lock(), "ab".repeat(50));
+    }
+
+    #[test]
+    fn test_compare_and_set() {
+        let counter = AtomicCounter::new(37);
+        assert_eq!(counter.compare_and_set(37, 73), Ok(37));
+        assert_eq!(counter.compare_and_set(37, 137), Err(73));
+        assert_eq!(counter.get(), 73);
+    }
+
+    #[test]
+    fn test_compare_and_set_lock_free_max_tracker() {
+        let max: &'static _ = Box::leak(Box::new(AtomicCounter::new(0)));
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                spawn(move || {
+                    for value in (0..1000).map(|j| (j * 7919 + i * 104_729) % 10_007) {
+                        let mut current = max.get();
+                        while value > current {
+                            match max.compare_and_set(current, value) {
+                                Ok(_) => break,
+                                Err(actual) => current = actual,
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let expected = (0..10)
+            .flat_map(|i| (0..1000).map(move |j| (j * 7919 + i * 104_729) % 10_007))
+            .max();
+        assert_eq!(Some(max.get()), expected);
+    }
+
+    fn increment_from_many_threads(increment: fn(&AtomicCounter) -> usize) -> usize {
+        let counter: &'static _ = Box::leak(Box::new(AtomicCounter::new(0)));
+        let handles: Vec<_> = (0..100)
+            .map(|_| {
+                spawn(move || {
+                    for _ in 0..1000 {
+                        increment(counter);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        counter.get()
+    }
+
+    #[test]
+    fn test_increment_relaxed() {
+        assert_eq!(
+            increment_from_many_threads(AtomicCounter::increment),
+            100 * 1000
+        );
+        assert_eq!(
+            increment_from_many_threads(|c| c.increment_with(Ordering::Relaxed)),
+            100 * 1000
+        );
+    }
+
+    #[test]
+    fn test_increment_seqcst() {
+        assert_eq!(
+            increment_from_many_threads(|c| c.increment_with(Ordering::SeqCst)),
+            100 * 1000
+        );
+    }
+
+    #[test]
+    fn test_increment_returns_previous_and_wraps() {
+        let counter = AtomicCounter::new(usize::MAX);
+        assert_eq!(counter.increment(), usize::MAX);
+        assert_eq!(counter.increment_with(Ordering::AcqRel), 0);
+        assert_eq!(counter.get(), 1);
+    }
+
+    #[test]
+    fn test_fetch_max_and_fetch_min() {
+        let counter = AtomicCounter::new(37);
+        assert_eq!(counter.fetch_max(10), 37);
+        assert_eq!(counter.fetch_max(73), 37);
+        assert_eq!(counter.get(), 73);
+        assert_eq!(counter.fetch_min(100), 73);
+        assert_eq!(counter.fetch_min(5), 73);
+        assert_eq!(counter.get(), 5);
+    }
+
+    #[test]
+    fn test_fetch_max_and_fetch_min_from_many_threads() {
+        let max: &'static _ = Box::leak(Box::new(AtomicCounter::new(0)));
+        let min: &'static _ = Box::leak(Box::new(AtomicCounter::new(usize::MAX)));
+        let value = |i: usize| (i * 7919 + 104_729) % 10_007;
+        let handles: Vec<_> = (0..100)
+            .map(|i| {
+                spawn(move || {
+                    max.fetch_max(value(i));
+                    min.fetch_min(value(i));
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(Some(max.get()), (0..100).map(value).max());
+        assert_eq!(Some(min.get()), (0..100).map(value).min());
+    }
+
+    #[test]
+    fn test_increment_saturating_stops_at_max() {
+        let counter: &'static _ = Box::leak(Box::new(AtomicCounter::new(usize::MAX - 50)));
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                spawn(move || {
+                    for _ in 0..10 {
+                        counter.increment_saturating();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.get(), usize::MAX);
+        assert_eq!(counter.increment_saturating(), usize::MAX);
+        assert_eq!(counter.get(), usize::MAX);
+    }
+
+    #[test]
+    fn test_increment_wrapping_wraps_past_max() {
+        let counter: &'static _ = Box::leak(Box::new(AtomicCounter::new(usize::MAX - 50)));
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                spawn(move || {
+                    for _ in 0..10 {
+                        counter.increment_wrapping();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.get(), 49);
+
+        let counter = AtomicCounter::new(usize::MAX);
+        assert_eq!(counter.increment_wrapping(), usize::MAX);
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[test]
+    fn test_would_block_display() {
+        use std::error::Error;
+        assert_eq!(WouldBlock.to_string(), "the lock is already held");
+        assert!(WouldBlock.source().is_none());
     }
 }
@@ -0,0 +1,129 @@
+use crate::rc::Rc;
+use crate::refcell::RefCell;
+
+/// A single-threaded pool of reusable byte buffers. Clones share the same buffers.
+pub struct BufferPool {
+    buffers: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        BufferPool {
+            buffers: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Hands out an empty buffer, reusing a returned one when available.
+    ///
+    /// The buffer goes back to the pool, cleared but keeping its capacity, when the
+    /// `PooledBuffer` is dropped.
+    pub fn acquire(&self) -> PooledBuffer {
+        let buffer = self
+            .buffers
+            .borrow_mut()
+            .expect("buffers are only borrowed while acquiring or returning one")
+            .pop()
+            .unwrap_or_default();
+        PooledBuffer {
+            buffer,
+            buffers: Rc::clone(&self.buffers),
+        }
+    }
+
+    /// Returns how many buffers are waiting in the pool to be reused.
+    pub fn available(&self) -> usize {
+        self.buffers
+            .borrow()
+            .expect("buffers are only borrowed while acquiring or returning one")
+            .len()
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for BufferPool {
+    fn clone(&self) -> Self {
+        BufferPool {
+            buffers: Rc::clone(&self.buffers),
+        }
+    }
+}
+
+/// A buffer borrowed from a `BufferPool`, returned to it when dropped.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    buffers: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl std::ops::Deref for PooledBuffer {
+    type Target = Vec<u8>;
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl std::ops::DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        self.buffers
+            .borrow_mut()
+            .expect("buffers are only borrowed while acquiring or returning one")
+            .push(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_returned_buffer_is_reused() {
+        let pool = BufferPool::new();
+        let mut buffer = pool.acquire();
+        buffer.extend_from_slice(&[0; 1024]);
+        let capacity = buffer.capacity();
+        let address = buffer.as_ptr();
+        drop(buffer);
+        assert_eq!(pool.available(), 1);
+
+        let buffer = pool.acquire();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+        assert_eq!(buffer.as_ptr(), address);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn test_acquire_from_empty_pool_allocates() {
+        let pool = BufferPool::new();
+        let first = pool.acquire();
+        let second = pool.clone().acquire();
+        assert_eq!(first.capacity(), 0);
+        assert_eq!(second.capacity(), 0);
+        drop(first);
+        drop(second);
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn test_buffers_outlive_the_pool_handle() {
+        let pool = BufferPool::new();
+        let mut buffer = pool.acquire();
+        let clone = pool.clone();
+        drop(pool);
+        buffer.push(37);
+        drop(buffer);
+        assert_eq!(clone.available(), 1);
+    }
+}
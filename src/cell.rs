@@ -1,6 +1,7 @@
 use std::cell::UnsafeCell;
 
-pub struct Cell<T> {
+#[repr(transparent)]
+pub struct Cell<T: ?Sized> {
     value: UnsafeCell<T>,
 }
 
@@ -27,6 +28,132 @@ impl<T> Cell<T> {
         // By setting new value no references are invalidated, because no reference was ever given.
         unsafe { *self.value.get() = value }
     }
+
+    /// Stores the result of `f` if it is `Ok`, returning the new value, and leaves the value
+    /// unchanged if it is `Err`.
+    pub fn try_update<E>(&self, f: impl FnOnce(T) -> Result<T, E>) -> Result<T, E>
+    where
+        T: Copy,
+    {
+        let new = f(self.get())?;
+        self.set(new);
+        Ok(new)
+    }
+
+    pub fn replace(&self, value: T) -> T {
+        // SAFETY: !Sync is implied because std::cell::UnsafeCell impl !Sync.
+        // No other thread is modifying this value, since only this thread can mutate.
+        // Swapping the values invalidates no references, because no reference was ever given.
+        unsafe { std::mem::replace(&mut *self.value.get(), value) }
+    }
+
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+}
+
+impl<T> Cell<[T]> {
+    /// Views the cell as a slice of cells, one per element.
+    pub fn as_slice_of_cells(&self) -> &[Cell<T>] {
+        // SAFETY: `Cell<T>` is `repr(transparent)` over `UnsafeCell<T>`, which has the same
+        // layout as `T`, so `Cell<[T]>` and `[Cell<T>]` have the same layout and length.
+        // Both only allow mutation through `Cell`, which is !Sync.
+        unsafe { &*(self as *const Cell<[T]> as *const [Cell<T>]) }
+    }
+}
+
+impl<T, const N: usize> Cell<[T; N]> {
+    /// Views the cell as an array of cells, one per element.
+    pub fn as_array_of_cells(&self) -> &[Cell<T>; N] {
+        // SAFETY: `Cell<T>` is `repr(transparent)` over `UnsafeCell<T>`, which has the same
+        // layout as `T`, so `Cell<[T; N]>` and `[Cell<T>; N]` have the same layout.
+        // Both only allow mutation through `Cell`, which is !Sync.
+        unsafe { &*(self as *const Cell<[T; N]> as *const [Cell<T>; N]) }
+    }
+
+    /// Returns a copy of the element at index `i`. Panics if `i` is out of bounds.
+    pub fn get_index(&self, i: usize) -> T
+    where
+        T: Copy,
+    {
+        self.as_array_of_cells()[i].get()
+    }
+
+    /// Sets the element at index `i`. Panics if `i` is out of bounds.
+    pub fn set_index(&self, i: usize, value: T) {
+        self.as_array_of_cells()[i].set(value)
+    }
+}
+
+impl<T> Cell<Option<T>> {
+    /// Stores `Some(value)` only if the cell holds `None`, returning whether it did.
+    ///
+    /// A value that is already there is left untouched, and `value` is dropped instead.
+    pub fn set_if_none(&self, value: T) -> bool {
+        // Option is not Copy, so check by taking the current value out and putting it back.
+        match self.take() {
+            Some(current) => {
+                self.set(Some(current));
+                false
+            }
+            None => {
+                self.set(Some(value));
+                true
+            }
+        }
+    }
+
+    /// Returns the stored value, first computing and storing it with `f` if the cell
+    /// holds `None`.
+    ///
+    /// `T: Copy`, like for `get`, since the value is returned by copy. `RefCell` provides
+    /// the same pattern for values that aren't `Copy`.
+    pub fn get_or_insert_with(&self, f: impl FnOnce() -> T) -> T
+    where
+        T: Copy,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        let value = f();
+        self.set(Some(value));
+        value
+    }
+}
+
+impl<T: PartialEq + Copy> PartialEq for Cell<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq + Copy> Eq for Cell<T> {}
+
+impl<T: PartialOrd + Copy> PartialOrd for Cell<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.get().partial_cmp(&other.get())
+    }
+}
+
+impl<T: Ord + Copy> Ord for Cell<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.get().cmp(&other.get())
+    }
+}
+
+impl<T: std::hash::Hash + Copy> std::hash::Hash for Cell<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl<T> From<T> for Cell<T> {
+    fn from(value: T) -> Self {
+        Cell::new(value)
+    }
 }
 
 #[cfg(test)]
@@ -69,4 +196,139 @@ mod tests {
         cell.set((0, 0, 0));
         assert_eq!(cell.get(), (0, 0, 0));
     }
+
+    #[test]
+    fn test_from() {
+        let cell: Cell<i32> = 5.into();
+        assert_eq!(cell.get(), 5);
+        assert_eq!(Cell::from(37).get(), 37);
+    }
+
+    #[test]
+    fn test_get_and_set_index() {
+        let cell = Cell::new([37, 73, 137]);
+        assert_eq!(cell.get_index(1), 73);
+        cell.set_index(1, 0);
+        assert_eq!(cell.get(), [37, 0, 137]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_index_out_of_bounds() {
+        let cell = Cell::new([37, 73, 137]);
+        cell.set_index(3, 0);
+    }
+
+    #[test]
+    fn test_as_array_of_cells() {
+        let cell = Cell::new([1, 2, 3, 4]);
+        let cells = cell.as_array_of_cells();
+        for element in cells {
+            element.set(element.get() * 10);
+        }
+        cells[0].set(cells[3].get());
+        assert_eq!(cell.get(), [40, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_as_slice_of_cells() {
+        let cell: &Cell<[i32]> = &Cell::new([1, 2, 3, 4, 5]);
+        let cells = cell.as_slice_of_cells();
+        assert_eq!(cells.len(), 5);
+        for element in cells.iter().step_by(2) {
+            element.set(-element.get());
+        }
+        let values: Vec<_> = cells.iter().map(Cell::get).collect();
+        assert_eq!(values, [-1, 2, -3, 4, -5]);
+    }
+
+    #[test]
+    fn test_same_layout_as_inner_type() {
+        const _: () = assert!(std::mem::size_of::<Cell<u64>>() == std::mem::size_of::<u64>());
+        const _: () = assert!(std::mem::align_of::<Cell<u64>>() == std::mem::align_of::<u64>());
+        assert_eq!(
+            std::mem::size_of::<Cell<[u8; 3]>>(),
+            std::mem::size_of::<[u8; 3]>()
+        );
+    }
+
+    #[test]
+    fn test_replace_and_take_non_copy() {
+        let cell = Cell::new(String::from("hello"));
+        assert_eq!(cell.replace(String::from("world")), "hello");
+        assert_eq!(cell.take(), "world");
+        assert_eq!(cell.take(), "");
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(Cell::new(37) == Cell::new(37));
+        assert!(Cell::new(37) < Cell::new(73));
+        assert_eq!(
+            Cell::new(73).cmp(&Cell::new(37)),
+            std::cmp::Ordering::Greater
+        );
+    }
+
+    // Keys must not be mutated while they are in a collection.
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn test_in_collections() {
+        let set: std::collections::HashSet<_> = [Cell::new(37), Cell::new(73), Cell::new(37)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&Cell::new(73)));
+
+        let set: std::collections::BTreeSet<_> = [Cell::new(73), Cell::new(37), Cell::new(137)]
+            .into_iter()
+            .collect();
+        let values: Vec<_> = set.iter().map(Cell::get).collect();
+        assert_eq!(values, [37, 73, 137]);
+    }
+
+    #[test]
+    fn test_try_update_accepted() {
+        let cell = Cell::new(37_u8);
+        assert_eq!(
+            cell.try_update(|v| v.checked_add(1).ok_or("overflow")),
+            Ok(38)
+        );
+        assert_eq!(cell.get(), 38);
+    }
+
+    #[test]
+    fn test_try_update_rejected() {
+        let cell = Cell::new(u8::MAX);
+        assert_eq!(
+            cell.try_update(|v| v.checked_add(1).ok_or("overflow")),
+            Err("overflow")
+        );
+        assert_eq!(cell.get(), u8::MAX);
+    }
+
+    #[test]
+    fn test_set_if_none() {
+        let cell = Cell::new(None);
+        assert!(cell.set_if_none(String::from("first")));
+        assert!(!cell.set_if_none(String::from("second")));
+        assert!(!cell.set_if_none(String::from("third")));
+        assert_eq!(cell.take().as_deref(), Some("first"));
+        assert!(cell.set_if_none(String::from("again")));
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let cell = Cell::new(None);
+        let calls = Cell::new(0);
+        let compute = || {
+            calls.set(calls.get() + 1);
+            42
+        };
+        assert_eq!(cell.get_or_insert_with(compute), 42);
+        assert_eq!(cell.get_or_insert_with(compute), 42);
+        assert_eq!(cell.get_or_insert_with(|| 7), 42);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(cell.get(), Some(42));
+    }
 }
@@ -0,0 +1,106 @@
+use crate::cell::Cell;
+
+/// A stack of at most `N` items that can be pushed and popped through a shared reference,
+/// for single-threaded use. Built only from `Cell`s, so it never hands out references
+/// to its items.
+pub struct CellStack<T, const N: usize> {
+    len: Cell<usize>,
+    slots: [Cell<Option<T>>; N],
+}
+
+impl<T, const N: usize> CellStack<T, N> {
+    pub fn new() -> Self {
+        CellStack {
+            len: Cell::new(0),
+            slots: std::array::from_fn(|_| Cell::new(None)),
+        }
+    }
+
+    /// Pushes `t` on top of the stack, or gives it back if the stack is full.
+    pub fn push(&self, t: T) -> Result<(), T> {
+        let len = self.len.get();
+        if len == N {
+            return Err(t);
+        }
+        self.slots[len].set(Some(t));
+        self.len.set(len + 1);
+        Ok(())
+    }
+
+    /// Removes and returns the top item, if any.
+    pub fn pop(&self) -> Option<T> {
+        let len = self.len.get().checked_sub(1)?;
+        self.len.set(len);
+        self.slots[len].take()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+}
+
+impl<T, const N: usize> Default for CellStack<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop_order() {
+        let stack: CellStack<i32, 4> = CellStack::new();
+        assert!(stack.is_empty());
+        stack.push(1).unwrap();
+        stack.push(2).unwrap();
+        stack.push(3).unwrap();
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.pop(), Some(3));
+        stack.push(4).unwrap();
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_push_on_full_stack_gives_value_back() {
+        let stack: CellStack<String, 2> = CellStack::new();
+        stack.push(String::from("a")).unwrap();
+        stack.push(String::from("b")).unwrap();
+        assert_eq!(stack.push(String::from("c")), Err(String::from("c")));
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.pop().as_deref(), Some("b"));
+        assert_eq!(stack.push(String::from("c")), Ok(()));
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let stack: CellStack<i32, 0> = CellStack::default();
+        assert_eq!(stack.push(37), Err(37));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn test_through_shared_reference() {
+        fn push_all(stack: &CellStack<i32, 8>, items: &[i32]) {
+            for &item in items {
+                stack.push(item).unwrap();
+            }
+        }
+
+        let stack = CellStack::new();
+        let (first, second) = (&stack, &stack);
+        push_all(first, &[1, 2]);
+        push_all(second, &[3]);
+        assert_eq!(first.pop(), Some(3));
+        assert_eq!(second.pop(), Some(2));
+    }
+}
@@ -1,14 +1,81 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, Condvar, Mutex};
 
+/// The error returned when receiving from a channel whose senders are all gone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvError {
+    Disconnected,
+}
+
+impl std::fmt::Display for RecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("receiving on a closed channel")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// The error returned when sending to a channel whose receiver is gone.
+/// It gives back the message that couldn't be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// Like std, doesn't require `T: Debug`, so `expect` works for any message type.
+impl<T> std::fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> std::fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("sending on a closed channel")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+/// The error returned by a non-blocking receive that found no message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No message is available right now, but senders remain.
+    Empty,
+    /// No message is available and all senders are gone.
+    Disconnected,
+}
+
+impl std::fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+            TryRecvError::Disconnected => f.write_str("receiving on an empty and closed channel"),
+        }
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
 struct Inner<T> {
     queue: VecDeque<T>,
+    /// The longest the queue has been, updated on each send.
+    max_len: usize,
     senders: usize,
+    receiver_alive: bool,
 }
 
 struct Shared<T> {
     inner: Mutex<Inner<T>>,
     available: Condvar,
+    notify: Notify,
+}
+
+impl<T> Shared<T> {
+    fn notify(&self) {
+        match self.notify {
+            Notify::One => self.available.notify_one(),
+            Notify::All => self.available.notify_all(),
+        }
+    }
 }
 
 pub struct Sender<T> {
@@ -16,11 +83,55 @@ pub struct Sender<T> {
 }
 
 impl<T> Sender<T> {
-    pub fn send(&mut self, t: T) {
+    pub fn send(&mut self, t: T) -> Result<(), SendError<T>> {
         let mut inner = self.shared.inner.lock().unwrap();
+        if !inner.receiver_alive {
+            return Err(SendError(t));
+        }
         inner.queue.push_back(t);
+        inner.max_len = inner.max_len.max(inner.queue.len());
+        drop(inner);
+        self.shared.notify();
+        Ok(())
+    }
+
+    /// Sends all `items` while holding the lock once, then wakes the receiver.
+    ///
+    /// Gives all items back if the receiver is gone.
+    pub fn send_all(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+    ) -> Result<(), SendError<Vec<T>>> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if !inner.receiver_alive {
+            return Err(SendError(items.into_iter().collect()));
+        }
+        inner.queue.extend(items);
+        inner.max_len = inner.max_len.max(inner.queue.len());
         drop(inner);
-        self.shared.available.notify_one();
+        self.shared.notify();
+        Ok(())
+    }
+
+    /// Closes this sender right away, waking the receiver if it was the last one.
+    ///
+    /// The same happens when a sender is dropped, this just makes the intent explicit.
+    /// Senders never block on this unbounded channel, so there is no sender to wake.
+    pub fn disconnect(self) {
+        drop(self);
+    }
+
+    /// Clones the sender, unless the receiver is already gone.
+    pub fn try_clone(&self) -> Option<Sender<T>> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if !inner.receiver_alive {
+            return None;
+        }
+        inner.senders += 1;
+        drop(inner);
+        Some(Sender {
+            shared: Arc::clone(&self.shared),
+        })
     }
 }
 
@@ -38,12 +149,18 @@ impl<T> Clone for Sender<T> {
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
         let mut inner = self.shared.inner.lock().unwrap();
+        // Every Sender is counted once when created, so this only trips on a bookkeeping bug,
+        // e.g. a Sender dropped twice or created without being counted.
+        debug_assert!(
+            inner.senders > 0,
+            "sender count underflow: more Senders dropped than were created"
+        );
         inner.senders -= 1;
         let was_last = inner.senders == 0;
         drop(inner);
 
         if was_last {
-            self.shared.available.notify_one();
+            self.shared.notify();
         }
     }
 }
@@ -51,6 +168,7 @@ impl<T> Drop for Sender<T> {
 pub struct Receiver<T> {
     shared: Arc<Shared<T>>,
     buffer: VecDeque<T>,
+    buffered: bool,
 }
 
 impl<T> Receiver<T> {
@@ -64,7 +182,9 @@ impl<T> Receiver<T> {
         loop {
             match inner.queue.pop_front() {
                 Some(t) => {
-                    std::mem::swap(&mut self.buffer, &mut inner.queue);
+                    if self.buffered {
+                        std::mem::swap(&mut self.buffer, &mut inner.queue);
+                    }
                     return Some(t);
                 }
                 None if inner.senders == 0 => return None,
@@ -74,6 +194,180 @@ impl<T> Receiver<T> {
             }
         }
     }
+
+    /// Blocks until at least one message is available, then returns up to `max` messages
+    /// taken under a single lock.
+    ///
+    /// Returns an empty `Vec` only once all senders are gone, or if `max` is 0.
+    pub fn receive_batch(&mut self, max: usize) -> Vec<T> {
+        let mut batch: Vec<T> = Vec::new();
+        if max == 0 {
+            return batch;
+        }
+        let from_buffer = self.buffer.len().min(max);
+        batch.extend(self.buffer.drain(..from_buffer));
+        if batch.len() == max {
+            return batch;
+        }
+
+        let mut inner = self.shared.inner.lock().unwrap();
+        while batch.is_empty() && inner.queue.is_empty() && inner.senders != 0 {
+            inner = self.shared.available.wait(inner).unwrap();
+        }
+        let from_queue = inner.queue.len().min(max - batch.len());
+        batch.extend(inner.queue.drain(..from_queue));
+        batch
+    }
+
+    /// Receives every message that arrives within `window`, then returns them all.
+    ///
+    /// Returns early, with what was received so far, once all senders are gone. A window
+    /// too large to express as a deadline, like `Duration::MAX`, waits for that alone.
+    pub fn collect_for(&mut self, window: std::time::Duration) -> Vec<T> {
+        let deadline = std::time::Instant::now().checked_add(window);
+        let mut collected: Vec<T> = self.buffer.drain(..).collect();
+
+        let mut inner = self.shared.inner.lock().unwrap();
+        loop {
+            collected.extend(inner.queue.drain(..));
+            if inner.senders == 0 {
+                return collected;
+            }
+            let Some(deadline) = deadline else {
+                inner = self.shared.available.wait(inner).unwrap();
+                continue;
+            };
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return collected;
+            }
+            inner = self
+                .shared
+                .available
+                .wait_timeout(inner, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Returns up to `n` messages already available, taken under a single lock, without blocking.
+    ///
+    /// Returns fewer, possibly none, if the channel holds less.
+    pub fn try_receive_n(&mut self, n: usize) -> Vec<T> {
+        let mut received: Vec<T> = Vec::new();
+        let from_buffer = self.buffer.len().min(n);
+        received.extend(self.buffer.drain(..from_buffer));
+        if received.len() == n {
+            return received;
+        }
+
+        let mut inner = self.shared.inner.lock().unwrap();
+        let from_queue = inner.queue.len().min(n - received.len());
+        received.extend(inner.queue.drain(..from_queue));
+        received
+    }
+
+    /// Receives messages for as long as `pred` holds for them, blocking between messages.
+    ///
+    /// The first message failing `pred` is only peeked: it stays in the channel and is
+    /// what the next receive returns. Also stops once all senders are gone.
+    pub fn receive_while(&mut self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut received = Vec::new();
+        while let Some(t) = self.receive() {
+            if !pred(&t) {
+                self.buffer.push_front(t);
+                break;
+            }
+            received.push(t);
+        }
+        received
+    }
+
+    /// Closes the receiving side right away, so every following `send` fails with `SendError`.
+    ///
+    /// The same happens when the receiver is dropped, this just makes the intent explicit.
+    pub fn disconnect(self) {
+        drop(self);
+    }
+
+    /// Receives a message if one is available, without blocking.
+    pub fn try_receive(&mut self) -> Result<T, TryRecvError> {
+        if let Some(t) = self.buffer.pop_front() {
+            return Ok(t);
+        }
+
+        let mut inner = self.shared.inner.lock().unwrap();
+        match inner.queue.pop_front() {
+            Some(t) => {
+                if self.buffered {
+                    std::mem::swap(&mut self.buffer, &mut inner.queue);
+                }
+                Ok(t)
+            }
+            None if inner.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Models readiness for async experiments: `Ready(Some(t))` with a message, `Ready(None)`
+    /// once all senders are gone, and `Pending` when it would have to wait.
+    ///
+    /// No waker is registered, so nothing wakes the caller when a message arrives;
+    /// an executor has to poll again on its own.
+    pub fn poll_receive(&mut self) -> std::task::Poll<Option<T>> {
+        match self.try_receive() {
+            Ok(t) => std::task::Poll::Ready(Some(t)),
+            Err(TryRecvError::Disconnected) => std::task::Poll::Ready(None),
+            Err(TryRecvError::Empty) => std::task::Poll::Pending,
+        }
+    }
+
+    /// Checks the local buffer and the shared queue under a single lock, so a message moving
+    /// between them can't be missed.
+    pub fn is_empty_consistent(&self) -> bool {
+        let inner = self.shared.inner.lock().unwrap();
+        self.buffer.is_empty() && inner.queue.is_empty()
+    }
+
+    /// Returns the most messages that were ever waiting in the shared queue at once.
+    ///
+    /// Messages already moved into the receiver's local buffer no longer count as queued.
+    pub fn high_water_mark(&self) -> usize {
+        self.shared.inner.lock().unwrap().max_len
+    }
+
+    /// Wraps the receiver so every message is passed through `f` as it is received.
+    pub fn map<U, F>(self, f: F) -> MappedReceiver<T, U, F>
+    where
+        F: FnMut(T) -> U,
+    {
+        MappedReceiver {
+            receiver: self,
+            f,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Blocks until all senders are gone, folding every message into the accumulator
+    /// with `f`, and returns the final value.
+    pub fn fold_blocking<B>(self, init: B, f: impl FnMut(B, T) -> B) -> B {
+        self.fold(init, f)
+    }
+
+    /// Turns the receiver into an iterator that yields `Ok` for every message and a final
+    /// `Err(RecvError::Disconnected)` once all senders are dropped, then ends.
+    pub fn into_try_iter(self) -> IntoTryIter<T> {
+        IntoTryIter {
+            receiver: self,
+            disconnected: false,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.inner.lock().unwrap().receiver_alive = false;
+    }
 }
 
 impl<T> Iterator for Receiver<T> {
@@ -83,25 +377,211 @@ impl<T> Iterator for Receiver<T> {
     }
 }
 
+pub struct IntoTryIter<T> {
+    receiver: Receiver<T>,
+    disconnected: bool,
+}
+
+impl<T> Iterator for IntoTryIter<T> {
+    type Item = Result<T, RecvError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.disconnected {
+            return None;
+        }
+        match self.receiver.receive() {
+            Some(t) => Some(Ok(t)),
+            None => {
+                self.disconnected = true;
+                Some(Err(RecvError::Disconnected))
+            }
+        }
+    }
+}
+
+/// A receiver that transforms each message as it is received, created by `Receiver::map`.
+pub struct MappedReceiver<T, U, F> {
+    receiver: Receiver<T>,
+    f: F,
+    // `U` is only produced, never stored.
+    _marker: std::marker::PhantomData<fn() -> U>,
+}
+
+impl<T, U, F> MappedReceiver<T, U, F>
+where
+    F: FnMut(T) -> U,
+{
+    /// Blocks like `Receiver::receive` and returns the transformed message.
+    pub fn receive(&mut self) -> Option<U> {
+        self.receiver.receive().map(&mut self.f)
+    }
+}
+
+impl<T, U, F> Iterator for MappedReceiver<T, U, F>
+where
+    F: FnMut(T) -> U,
+{
+    type Item = U;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receive()
+    }
+}
+
+/// How a send wakes threads waiting on the channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notify {
+    /// Wake a single waiting thread.
+    #[default]
+    One,
+    /// Wake every waiting thread.
+    All,
+}
+
+/// Configures the tradeoffs a channel makes internally.
+pub struct ChannelBuilder {
+    notify: Notify,
+    buffered: bool,
+    initial_capacity: usize,
+}
+
+impl ChannelBuilder {
+    /// Starts with the defaults used by `channel()`: `Notify::One`, buffering enabled and
+    /// no preallocated room.
+    pub fn new() -> Self {
+        ChannelBuilder {
+            notify: Notify::One,
+            buffered: true,
+            initial_capacity: 0,
+        }
+    }
+
+    /// Sets how sends and the last dropped sender wake waiting threads.
+    pub fn notify(mut self, notify: Notify) -> Self {
+        self.notify = notify;
+        self
+    }
+
+    /// Sets whether the receiver swaps the whole shared queue into its local buffer
+    /// on each lock, instead of taking one message per lock.
+    pub fn buffered(mut self, buffered: bool) -> Self {
+        self.buffered = buffered;
+        self
+    }
+
+    /// Preallocates room for `initial` messages in the shared queue.
+    ///
+    /// Only a hint: the channel stays unbounded and grows past it like any `VecDeque`.
+    pub fn initial_capacity(mut self, initial: usize) -> Self {
+        self.initial_capacity = initial;
+        self
+    }
+
+    pub fn build<T>(self) -> (Sender<T>, Receiver<T>) {
+        let inner = Inner {
+            queue: VecDeque::with_capacity(self.initial_capacity),
+            max_len: 0,
+            senders: 1,
+            receiver_alive: true,
+        };
+        let shared = Shared {
+            inner: Mutex::new(inner),
+            available: Condvar::new(),
+            notify: self.notify,
+        };
+        let shared = Arc::new(shared);
+        (
+            Sender {
+                shared: shared.clone(),
+            },
+            Receiver {
+                shared: shared.clone(),
+                buffer: Default::default(),
+                buffered: self.buffered,
+            },
+        )
+    }
+}
+
+impl Default for ChannelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
-    let inner = Inner {
-        queue: VecDeque::new(),
-        senders: 1,
-    };
-    let shared = Shared {
-        inner: Mutex::new(inner),
-        available: Condvar::new(),
-    };
-    let shared = Arc::new(shared);
-    (
-        Sender {
-            shared: shared.clone(),
-        },
-        Receiver {
-            shared: shared.clone(),
-            buffer: Default::default(),
-        },
-    )
+    ChannelBuilder::new().build()
+}
+
+/// Like `channel()`, but preallocates room for `initial` messages to avoid early
+/// reallocations when the message volume is known.
+///
+/// This is not a bounded channel: sends never block, the queue just grows past `initial`.
+pub fn channel_with_capacity<T>(initial: usize) -> (Sender<T>, Receiver<T>) {
+    ChannelBuilder::new().initial_capacity(initial).build()
+}
+
+pub struct MergeReceivers<T> {
+    receivers: Vec<Receiver<T>>,
+    next: usize,
+}
+
+impl<T> Iterator for MergeReceivers<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut attempts = self.receivers.len();
+            while attempts > 0 && !self.receivers.is_empty() {
+                let index = self.next % self.receivers.len();
+                match self.receivers[index].try_receive() {
+                    Ok(t) => {
+                        self.next = index + 1;
+                        return Some(t);
+                    }
+                    Err(TryRecvError::Empty) => self.next = index + 1,
+                    Err(TryRecvError::Disconnected) => {
+                        // The next receiver moves into this index.
+                        self.receivers.remove(index);
+                        self.next = index;
+                    }
+                }
+                attempts -= 1;
+            }
+
+            if self.receivers.is_empty() {
+                return None;
+            }
+            // All remaining receivers are empty, wait a little instead of busy spinning.
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+}
+
+/// Receives from all `receivers` in round-robin order, one message from each in turn.
+///
+/// Only waits when every receiver is empty, and ends once all of them are disconnected.
+pub fn merge_receivers<T>(receivers: Vec<Receiver<T>>) -> MergeReceivers<T> {
+    MergeReceivers { receivers, next: 0 }
+}
+
+/// Blocks until any of `receivers` has a message, returning its index and the message.
+///
+/// Receivers are checked in slice order, so the lowest ready index wins. Returns `None`
+/// once every receiver is disconnected and empty, right away for an empty slice.
+pub fn select_all<T>(receivers: &mut [Receiver<T>]) -> Option<(usize, T)> {
+    loop {
+        let mut disconnected = 0;
+        for (index, receiver) in receivers.iter_mut().enumerate() {
+            match receiver.try_receive() {
+                Ok(t) => return Some((index, t)),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => disconnected += 1,
+            }
+        }
+        if disconnected == receivers.len() {
+            return None;
+        }
+        // Like `merge_receivers`, wait a little instead of busy spinning.
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
 }
 
 #[cfg(test)]
@@ -112,15 +592,15 @@ mod tests {
     #[test]
     fn test_send_and_receive() {
         let (mut tx, mut rx) = channel();
-        tx.send(37);
+        tx.send(37).unwrap();
         assert_eq!(rx.receive(), Some(37));
     }
 
     #[test]
     fn test_multiple_send_receive() {
         let (mut tx, mut rx) = channel();
-        tx.send(1);
-        tx.send(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
         assert_eq!(rx.receive(), Some(1));
         assert_eq!(rx.receive(), Some(2));
     }
@@ -136,7 +616,100 @@ mod tests {
     fn test_closed_rx() {
         let (mut tx, rx) = channel();
         drop(rx);
-        tx.send(42);
+        assert_eq!(tx.send(42), Err(SendError(42)));
+    }
+
+    #[test]
+    fn test_send_all() {
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || (0..1000).map(|_| rx.receive().unwrap()).sum::<i32>());
+        thread::sleep(std::time::Duration::from_millis(10));
+        tx.send_all(0..1000).unwrap();
+        assert_eq!(handle.join().unwrap(), (0..1000).sum::<i32>());
+    }
+
+    #[test]
+    fn test_send_all_keeps_order() {
+        let (mut tx, rx) = channel();
+        tx.send(0).unwrap();
+        tx.send_all(vec![1, 2, 3]).unwrap();
+        tx.send(4).unwrap();
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_send_all_after_receiver_dropped() {
+        let (mut tx, rx) = channel();
+        drop(rx);
+        assert_eq!(tx.send_all([1, 2]), Err(SendError(vec![1, 2])));
+    }
+
+    #[test]
+    fn test_receive_batch() {
+        let (mut tx, mut rx) = channel();
+        tx.send_all(0..5).unwrap();
+        assert_eq!(rx.receive_batch(3), [0, 1, 2]);
+        tx.send(5).unwrap();
+        assert_eq!(rx.receive_batch(10), [3, 4, 5]);
+        drop(tx);
+        assert!(rx.receive_batch(10).is_empty());
+    }
+
+    #[test]
+    fn test_receive_batch_takes_from_buffer_first() {
+        let (mut tx, mut rx) = channel();
+        tx.send_all(0..3).unwrap();
+        // Moves the rest of the queue into the receiver's local buffer.
+        assert_eq!(rx.receive(), Some(0));
+        tx.send_all(3..6).unwrap();
+        assert_eq!(rx.receive_batch(4), [1, 2, 3, 4]);
+        assert_eq!(rx.receive_batch(4), [5]);
+    }
+
+    #[test]
+    fn test_receive_batch_blocks_until_send() {
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || rx.receive_batch(10));
+        thread::sleep(std::time::Duration::from_millis(50));
+        tx.send_all([37, 73]).unwrap();
+        assert_eq!(handle.join().unwrap(), [37, 73]);
+    }
+
+    #[test]
+    fn test_receiver_disconnect() {
+        let (mut tx, rx) = channel();
+        tx.send(1).unwrap();
+        rx.disconnect();
+        assert_eq!(tx.send(2), Err(SendError(2)));
+        assert!(tx.try_clone().is_none());
+    }
+
+    #[test]
+    fn test_sender_disconnect_wakes_receiver() {
+        let (tx, mut rx) = channel::<()>();
+        let handle = thread::spawn(move || rx.receive());
+        thread::sleep(std::time::Duration::from_millis(50));
+        tx.disconnect();
+        assert_eq!(handle.join().unwrap(), None);
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let (tx, mut rx) = channel();
+        let mut tx_clone = tx.try_clone().unwrap();
+        tx_clone.send(37).unwrap();
+        drop(tx);
+        drop(tx_clone);
+        assert_eq!(rx.receive(), Some(37));
+        assert_eq!(rx.receive(), None);
+    }
+
+    #[test]
+    fn test_try_clone_after_receiver_dropped() {
+        let (tx, rx) = channel::<()>();
+        drop(rx);
+        assert!(tx.try_clone().is_none());
     }
 
     #[test]
@@ -147,12 +720,12 @@ mod tests {
         for i in 0..10 {
             let mut tx_clone = tx.clone();
             let handle = thread::spawn(move || {
-                tx_clone.send(i);
+                tx_clone.send(i).unwrap();
             });
             handles.push(handle);
         }
 
-        tx.send(10);
+        tx.send(10).unwrap();
 
         for handle in handles {
             handle.join().unwrap();
@@ -171,7 +744,420 @@ mod tests {
             assert_eq!(rx.receive(), Some(37));
         });
         thread::sleep(std::time::Duration::from_millis(100));
-        tx.send(37);
+        tx.send(37).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_is_empty_consistent() {
+        let (mut tx, mut rx) = channel();
+        assert!(rx.is_empty_consistent());
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert!(!rx.is_empty_consistent());
+        assert_eq!(rx.receive(), Some(1));
+        // The remaining message now sits in the receiver's local buffer.
+        assert!(!rx.is_empty_consistent());
+        assert_eq!(rx.receive(), Some(2));
+        assert!(rx.is_empty_consistent());
+    }
+
+    #[test]
+    fn test_is_empty_consistent_under_concurrent_sends() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let sent: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || {
+            for i in 0..10_000 {
+                tx.send(i).unwrap();
+                sent.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let mut received = 0;
+        while rx.receive().is_some() {
+            received += 1;
+            if sent.load(Ordering::SeqCst) > received {
+                assert!(!rx.is_empty_consistent());
+            }
+        }
+        handle.join().unwrap();
+        assert_eq!(received, 10_000);
+    }
+
+    fn send_from_many_threads_and_receive(builder: ChannelBuilder) {
+        let (tx, mut rx) = builder.build();
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let mut tx = tx.clone();
+                thread::spawn(move || {
+                    for j in 0..100 {
+                        tx.send(i * 100 + j).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+
+        let mut received_values: Vec<_> = (&mut rx).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        received_values.sort_unstable();
+        assert_eq!(received_values, (0..1000).collect::<Vec<_>>());
+        assert_eq!(rx.receive(), None);
+    }
+
+    #[test]
+    fn test_builder_notify_one() {
+        send_from_many_threads_and_receive(ChannelBuilder::new().notify(Notify::One));
+    }
+
+    #[test]
+    fn test_builder_notify_all() {
+        send_from_many_threads_and_receive(ChannelBuilder::new().notify(Notify::All));
+    }
+
+    #[test]
+    fn test_builder_unbuffered() {
+        send_from_many_threads_and_receive(ChannelBuilder::new().buffered(false));
+        send_from_many_threads_and_receive(
+            ChannelBuilder::new().notify(Notify::All).buffered(false),
+        );
+    }
+
+    #[test]
+    fn test_builder_unbuffered_leaves_messages_in_queue() {
+        let (mut tx, mut rx) = ChannelBuilder::new().buffered(false).build();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.receive(), Some(1));
+        assert!(rx.buffer.is_empty());
+        assert_eq!(rx.shared.inner.lock().unwrap().queue.len(), 1);
+    }
+
+    #[test]
+    fn test_try_receive() {
+        let (mut tx, mut rx) = channel();
+        assert_eq!(rx.try_receive(), Err(TryRecvError::Empty));
+        tx.send(37).unwrap();
+        assert_eq!(rx.try_receive(), Ok(37));
+        drop(tx);
+        assert_eq!(rx.try_receive(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_merge_receivers_round_robin() {
+        let (mut tx_a, rx_a) = channel();
+        let (mut tx_b, rx_b) = channel();
+        for i in 0..3 {
+            tx_a.send(i).unwrap();
+            tx_b.send(i + 100).unwrap();
+        }
+        tx_a.send(3).unwrap();
+        drop(tx_a);
+        drop(tx_b);
+        let merged: Vec<_> = merge_receivers(vec![rx_a, rx_b]).collect();
+        assert_eq!(merged, [0, 100, 1, 101, 2, 102, 3]);
+    }
+
+    #[test]
+    fn test_merge_receivers_waits_for_slow_senders() {
+        let (mut tx_a, rx_a) = channel();
+        let (mut tx_b, rx_b) = channel();
+        let handle = thread::spawn(move || {
+            for i in 0..10 {
+                tx_a.send(i).unwrap();
+                tx_b.send(i + 100).unwrap();
+                thread::sleep(std::time::Duration::from_millis(2));
+            }
+        });
+        let mut merged: Vec<_> = merge_receivers(vec![rx_a, rx_b]).collect();
         handle.join().unwrap();
+        assert_eq!(merged.len(), 20);
+        let from_a = merged.iter().take(10).filter(|value| **value < 100).count();
+        assert!((3..=7).contains(&from_a), "unbalanced: {merged:?}");
+        merged.sort_unstable();
+        assert_eq!(merged, (0..10).chain(100..110).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_into_try_iter_reports_disconnection() {
+        let (mut tx, rx) = channel();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+        let mut iter = rx.into_try_iter();
+        assert_eq!(iter.next(), Some(Ok(1)));
+        assert_eq!(iter.next(), Some(Ok(2)));
+        assert_eq!(iter.next(), Some(Err(RecvError::Disconnected)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_high_water_mark() {
+        let (mut tx, mut rx) = channel();
+        assert_eq!(rx.high_water_mark(), 0);
+        tx.send_all(0..3).unwrap();
+        tx.send(3).unwrap();
+        assert_eq!(rx.high_water_mark(), 4);
+        assert_eq!(rx.receive_batch(10), [0, 1, 2, 3]);
+
+        // A smaller burst doesn't lower the mark.
+        tx.send_all(0..2).unwrap();
+        assert_eq!(rx.receive_batch(10), [0, 1]);
+        assert_eq!(rx.high_water_mark(), 4);
+
+        // A longer one raises it, even if part of the previous burst was received in between.
+        tx.send_all(0..5).unwrap();
+        assert_eq!(rx.receive_batch(2), [0, 1]);
+        tx.send_all(5..8).unwrap();
+        assert_eq!(rx.high_water_mark(), 6);
+    }
+
+    #[test]
+    fn test_map() {
+        let (mut tx, rx) = channel();
+        let mut rx = rx.map(|n: i32| format!("#{n}"));
+        tx.send(37).unwrap();
+        assert_eq!(rx.receive(), Some(String::from("#37")));
+
+        let handle = thread::spawn(move || rx.collect::<Vec<_>>());
+        tx.send_all([1, 2]).unwrap();
+        drop(tx);
+        assert_eq!(handle.join().unwrap(), ["#1", "#2"]);
+    }
+
+    #[test]
+    fn test_receive_while() {
+        let (mut tx, mut rx) = channel();
+        tx.send_all([1, 2, 3, -1, 4]).unwrap();
+        assert_eq!(rx.receive_while(|&n| n > 0), [1, 2, 3]);
+        // The sentinel is left for the next receive.
+        assert_eq!(rx.receive(), Some(-1));
+        drop(tx);
+        assert_eq!(rx.receive_while(|&n| n > 0), [4]);
+    }
+
+    #[test]
+    fn test_receive_while_unbuffered() {
+        let (mut tx, mut rx) = ChannelBuilder::new().buffered(false).build();
+        tx.send_all([1, -1, 2]).unwrap();
+        assert_eq!(rx.receive_while(|&n| n > 0), [1]);
+        assert_eq!(rx.try_receive(), Ok(-1));
+        assert_eq!(rx.try_receive(), Ok(2));
+    }
+
+    #[test]
+    fn test_try_receive_n() {
+        let (mut tx, mut rx) = channel();
+        assert!(rx.try_receive_n(3).is_empty());
+        tx.send_all(0..5).unwrap();
+        assert_eq!(rx.try_receive_n(3), [0, 1, 2]);
+        assert_eq!(rx.try_receive_n(3), [3, 4]);
+        assert!(rx.try_receive_n(3).is_empty());
+        drop(tx);
+        assert!(rx.try_receive_n(3).is_empty());
+    }
+
+    #[test]
+    fn test_try_receive_n_takes_from_buffer_first() {
+        let (mut tx, mut rx) = channel();
+        tx.send_all(0..3).unwrap();
+        // Moves the rest of the queue into the receiver's local buffer.
+        assert_eq!(rx.receive(), Some(0));
+        tx.send_all(3..6).unwrap();
+        assert_eq!(rx.try_receive_n(4), [1, 2, 3, 4]);
+        assert_eq!(rx.try_receive_n(0), Vec::<i32>::new());
+        assert_eq!(rx.try_receive_n(4), [5]);
+    }
+
+    #[test]
+    fn test_collect_for() {
+        use std::time::Duration;
+
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                tx.send(i).unwrap();
+                thread::sleep(Duration::from_millis(10));
+            }
+            thread::sleep(Duration::from_millis(400));
+            tx.send(3).unwrap();
+        });
+        assert_eq!(rx.collect_for(Duration::from_millis(100)), [0, 1, 2]);
+        handle.join().unwrap();
+        assert_eq!(rx.collect_for(Duration::from_millis(100)), [3]);
+    }
+
+    #[test]
+    fn test_collect_for_without_deadline() {
+        use std::time::Duration;
+
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || {
+            tx.send(37).unwrap();
+            thread::sleep(Duration::from_millis(10));
+            tx.send(73).unwrap();
+        });
+        assert_eq!(rx.collect_for(Duration::MAX), [37, 73]);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_collect_for_returns_early_on_disconnect() {
+        use std::time::{Duration, Instant};
+
+        let (mut tx, mut rx) = channel();
+        let handle = thread::spawn(move || {
+            tx.send_all([37, 73]).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        });
+        let start = Instant::now();
+        assert_eq!(rx.collect_for(Duration::from_secs(10)), [37, 73]);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_poll_receive() {
+        use std::task::Poll;
+
+        let (mut tx, mut rx) = channel();
+        assert_eq!(rx.poll_receive(), Poll::Pending);
+        tx.send(37).unwrap();
+        assert_eq!(rx.poll_receive(), Poll::Ready(Some(37)));
+        assert_eq!(rx.poll_receive(), Poll::Pending);
+        tx.send(73).unwrap();
+        drop(tx);
+        assert_eq!(rx.poll_receive(), Poll::Ready(Some(73)));
+        assert_eq!(rx.poll_receive(), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_sender_count_through_clone_and_drop_cycles() {
+        let (tx, mut rx) = channel::<i32>();
+        let senders = |tx: &Sender<i32>| tx.shared.inner.lock().unwrap().senders;
+        for round in 1..=3 {
+            let clones: Vec<_> = (0..round).map(|_| tx.clone()).collect();
+            let extra = clones[0].try_clone().unwrap();
+            assert_eq!(senders(&tx), 1 + round + 1);
+            drop(clones);
+            extra.disconnect();
+            assert_eq!(senders(&tx), 1);
+        }
+        drop(tx);
+        assert_eq!(rx.receive(), None);
+    }
+
+    #[test]
+    fn test_receive_keeps_waiting_after_spurious_wakeup() {
+        use std::time::Duration;
+
+        let (mut tx, mut rx) = channel();
+        let shared = Arc::clone(&rx.shared);
+        let handle = thread::spawn(move || (rx.receive(), rx.receive_batch(10)));
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(20));
+            // Wakes the receiver without a message, like a spurious wakeup would.
+            shared.available.notify_all();
+        }
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+
+        tx.send(37).unwrap();
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(20));
+            shared.available.notify_all();
+        }
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+
+        tx.send(73).unwrap();
+        assert_eq!(handle.join().unwrap(), (Some(37), vec![73]));
+    }
+
+    #[test]
+    fn test_channel_with_capacity() {
+        let (mut tx, mut rx) = channel_with_capacity(64);
+        assert!(rx.shared.inner.lock().unwrap().queue.capacity() >= 64);
+        tx.send_all(0..100).unwrap();
+        assert_eq!(rx.receive(), Some(0));
+        assert_eq!(rx.try_receive_n(200), (1..100).collect::<Vec<_>>());
+        drop(tx);
+        assert_eq!(rx.receive(), None);
+    }
+
+    #[test]
+    fn test_builder_initial_capacity() {
+        send_from_many_threads_and_receive(ChannelBuilder::new().initial_capacity(16));
+    }
+
+    #[test]
+    fn test_fold_blocking() {
+        let (mut tx, rx) = channel();
+        let worker = thread::spawn(move || {
+            for i in 1..=10 {
+                tx.send(i).unwrap();
+            }
+        });
+        assert_eq!(rx.fold_blocking(0, |sum, i| sum + i), 55);
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_error_display() {
+        use std::error::Error;
+        let errors: [(&dyn Error, &str); 4] = [
+            (&RecvError::Disconnected, "receiving on a closed channel"),
+            (&SendError(37), "sending on a closed channel"),
+            (&TryRecvError::Empty, "receiving on an empty channel"),
+            (
+                &TryRecvError::Disconnected,
+                "receiving on an empty and closed channel",
+            ),
+        ];
+        for (error, message) in errors {
+            assert_eq!(error.to_string(), message);
+            assert!(error.source().is_none());
+        }
+    }
+
+    #[test]
+    fn test_errors_work_with_question_mark() {
+        fn send_one() -> Result<(), Box<dyn std::error::Error>> {
+            let (mut tx, rx) = channel();
+            drop(rx);
+            tx.send(String::from("lost"))?;
+            Ok(())
+        }
+        assert_eq!(
+            send_one().unwrap_err().to_string(),
+            "sending on a closed channel"
+        );
+    }
+
+    #[test]
+    fn test_select_all_returns_ready_index() {
+        let (senders, mut receivers): (Vec<_>, Vec<_>) = (0..3).map(|_| channel()).unzip();
+        let mut senders = senders.into_iter();
+        let (first, second, mut third) = (
+            senders.next().unwrap(),
+            senders.next().unwrap(),
+            senders.next().unwrap(),
+        );
+        let worker = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(10));
+            third.send(37).unwrap();
+        });
+        assert_eq!(select_all(&mut receivers), Some((2, 37)));
+        worker.join().unwrap();
+        drop((first, second));
+        assert_eq!(select_all(&mut receivers), None);
+    }
+
+    #[test]
+    fn test_select_all_without_receivers() {
+        assert_eq!(select_all::<i32>(&mut []), None);
     }
 }
@@ -0,0 +1,64 @@
+/// Runs the stored closure when dropped, e.g. on scope exit or early return.
+#[must_use = "the closure runs as soon as the guard is dropped"]
+pub struct Defer<F: FnOnce()> {
+    f: Option<F>,
+}
+
+impl<F: FnOnce()> Drop for Defer<F> {
+    fn drop(&mut self) {
+        if let Some(f) = self.f.take() {
+            f();
+        }
+    }
+}
+
+/// Returns a guard that runs `f` when it goes out of scope.
+#[must_use = "the closure runs as soon as the guard is dropped"]
+pub fn defer<F: FnOnce()>(f: F) -> Defer<F> {
+    Defer { f: Some(f) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn test_defer_runs_on_scope_exit() {
+        let flag = Cell::new(false);
+        {
+            let _guard = defer(|| flag.set(true));
+            assert!(!flag.get());
+        }
+        assert!(flag.get());
+    }
+
+    #[test]
+    fn test_defer_runs_on_early_return() {
+        fn early_return(flag: &Cell<bool>, bail: bool) -> u32 {
+            let _guard = defer(|| flag.set(true));
+            if bail {
+                return 0;
+            }
+            37
+        }
+
+        let flag = Cell::new(false);
+        assert_eq!(early_return(&flag, true), 0);
+        assert!(flag.get());
+
+        flag.set(false);
+        assert_eq!(early_return(&flag, false), 37);
+        assert!(flag.get());
+    }
+
+    #[test]
+    fn test_defer_runs_in_reverse_order() {
+        let order = Cell::new(0);
+        {
+            let _first = defer(|| order.set(order.get() * 10 + 1));
+            let _second = defer(|| order.set(order.get() * 10 + 2));
+        }
+        assert_eq!(order.get(), 21);
+    }
+}
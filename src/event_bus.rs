@@ -0,0 +1,120 @@
+use crate::rc::Rc;
+use crate::refcell::RefCell;
+
+/// The error returned when a callback tries to subscribe while the bus is emitting an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubscribeError;
+
+impl std::fmt::Display for SubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("can't subscribe while the bus is emitting an event")
+    }
+}
+
+impl std::error::Error for SubscribeError {}
+
+type Callback<T> = Box<dyn Fn(&T)>;
+
+/// A single-threaded event bus. Clones share the same list of subscribers.
+pub struct EventBus<T> {
+    subscribers: Rc<RefCell<Vec<Callback<T>>>>,
+}
+
+impl<T> EventBus<T> {
+    pub fn new() -> Self {
+        EventBus {
+            subscribers: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Registers `callback` to be called on every emitted event.
+    ///
+    /// Fails with `SubscribeError` when called from inside a callback during `emit`,
+    /// because the subscriber list is already borrowed for iteration.
+    pub fn subscribe(&self, callback: impl Fn(&T) + 'static) -> Result<(), SubscribeError> {
+        let mut subscribers = self.subscribers.borrow_mut().ok_or(SubscribeError)?;
+        subscribers.push(Box::new(callback));
+        Ok(())
+    }
+
+    /// Calls every subscriber with `event`, in subscription order.
+    pub fn emit(&self, event: &T) {
+        let subscribers = self
+            .subscribers
+            .borrow()
+            .expect("subscribers are only borrowed mutably while subscribing");
+        for callback in subscribers.iter() {
+            callback(event);
+        }
+    }
+}
+
+impl<T> Default for EventBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for EventBus<T> {
+    fn clone(&self) -> Self {
+        EventBus {
+            subscribers: Rc::clone(&self.subscribers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn test_emit_calls_every_subscriber() {
+        let bus = EventBus::new();
+        let total = Rc::new(Cell::new(0));
+        for factor in 1..=3 {
+            let total = Rc::clone(&total);
+            bus.subscribe(move |event: &i32| total.set(total.get() + event * factor))
+                .unwrap();
+        }
+        bus.emit(&1);
+        bus.emit(&10);
+        assert_eq!(total.get(), 6 + 60);
+    }
+
+    #[test]
+    fn test_clones_share_subscribers() {
+        let bus = EventBus::new();
+        let seen = Rc::new(Cell::new(0));
+        let seen_clone = Rc::clone(&seen);
+        bus.clone()
+            .subscribe(move |event: &i32| seen_clone.set(*event))
+            .unwrap();
+        bus.emit(&37);
+        assert_eq!(seen.get(), 37);
+    }
+
+    #[test]
+    fn test_subscribe_during_emit_returns_error() {
+        let bus = EventBus::new();
+        let result = Rc::new(Cell::new(None));
+        let bus_clone = bus.clone();
+        let result_clone = Rc::clone(&result);
+        bus.subscribe(move |_: &()| result_clone.set(Some(bus_clone.subscribe(|_| {}))))
+            .unwrap();
+        bus.emit(&());
+        assert_eq!(result.get(), Some(Err(SubscribeError)));
+        // The bus is usable again once emit returns.
+        assert_eq!(bus.subscribe(|_| {}), Ok(()));
+    }
+
+    #[test]
+    fn test_subscribe_error_display() {
+        use std::error::Error;
+        assert_eq!(
+            SubscribeError.to_string(),
+            "can't subscribe while the bus is emitting an event"
+        );
+        assert!(SubscribeError.source().is_none());
+    }
+}
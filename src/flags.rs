@@ -0,0 +1,154 @@
+use crate::cell::Cell;
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl};
+
+/// The integer operations `Flags` needs, implemented for every type providing them,
+/// such as the unsigned integers.
+pub trait FlagBits:
+    Copy
+    + PartialEq
+    + From<u8>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+{
+}
+
+impl<B> FlagBits for B where
+    B: Copy
+        + PartialEq
+        + From<u8>
+        + BitAnd<Output = B>
+        + BitOr<Output = B>
+        + BitXor<Output = B>
+        + Not<Output = B>
+        + Shl<u32, Output = B>
+{
+}
+
+/// A set of bit flags that can be changed through a shared reference, for single-threaded use.
+///
+/// Backed by a `u32` by default, but any unsigned integer type works.
+pub struct Flags<B = u32> {
+    bits: Cell<B>,
+}
+
+impl<B: FlagBits> Flags<B> {
+    /// Creates a set with no bits set.
+    pub fn new() -> Self {
+        Self::from_bits(B::from(0))
+    }
+
+    /// Creates a set from its raw bits.
+    pub fn from_bits(bits: B) -> Self {
+        Flags {
+            bits: Cell::new(bits),
+        }
+    }
+
+    /// Returns the raw bits.
+    pub fn bits(&self) -> B {
+        self.bits.get()
+    }
+
+    /// Sets `bit`, counted from the least significant bit.
+    ///
+    /// Like the other methods, panics if `bit` doesn't fit in `B`.
+    pub fn insert(&self, bit: u32) {
+        self.bits.set(self.bits.get() | Self::mask(bit));
+    }
+
+    /// Clears `bit`.
+    pub fn remove(&self, bit: u32) {
+        self.bits.set(self.bits.get() & !Self::mask(bit));
+    }
+
+    /// Flips `bit`.
+    pub fn toggle(&self, bit: u32) {
+        self.bits.set(self.bits.get() ^ Self::mask(bit));
+    }
+
+    /// Returns whether `bit` is set.
+    pub fn contains(&self, bit: u32) -> bool {
+        self.bits.get() & Self::mask(bit) != B::from(0)
+    }
+
+    fn mask(bit: u32) -> B {
+        let width = std::mem::size_of::<B>() * 8;
+        assert!(
+            (bit as usize) < width,
+            "bit {bit} out of range for {width} bits"
+        );
+        B::from(1) << bit
+    }
+}
+
+impl<B: FlagBits> Default for Flags<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_remove() {
+        let flags: Flags = Flags::new();
+        flags.insert(0);
+        flags.insert(5);
+        assert!(flags.contains(0));
+        assert!(flags.contains(5));
+        assert!(!flags.contains(1));
+        assert_eq!(flags.bits(), 0b10_0001);
+
+        flags.remove(0);
+        flags.remove(1);
+        assert!(!flags.contains(0));
+        assert_eq!(flags.bits(), 0b10_0000);
+    }
+
+    #[test]
+    fn test_toggle() {
+        let flags = Flags::from_bits(0b1_u32);
+        flags.toggle(0);
+        flags.toggle(31);
+        assert!(!flags.contains(0));
+        assert!(flags.contains(31));
+        flags.toggle(31);
+        assert_eq!(flags.bits(), 0);
+    }
+
+    #[test]
+    fn test_other_backing_types() {
+        let small: Flags<u8> = Flags::default();
+        small.insert(7);
+        assert_eq!(small.bits(), 0x80);
+
+        let large: Flags<u128> = Flags::new();
+        large.insert(100);
+        assert!(large.contains(100));
+        assert_eq!(large.bits(), 1 << 100);
+    }
+
+    #[test]
+    fn test_through_shared_reference() {
+        fn enable(flags: &Flags, bit: u32) {
+            flags.insert(bit);
+        }
+
+        let flags = Flags::new();
+        let shared = &flags;
+        enable(shared, 3);
+        enable(shared, 4);
+        assert_eq!(flags.bits(), 0b1_1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit 8 out of range for 8 bits")]
+    fn test_bit_out_of_range() {
+        Flags::<u8>::new().insert(8);
+    }
+}
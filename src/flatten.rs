@@ -6,6 +6,30 @@ where
     Flatten::new(iter.into_iter())
 }
 
+/// Flattens `nested` by reference, yielding references to the inner items.
+///
+/// Works for any collection whose references can be iterated, such as `&Vec<Vec<T>>`.
+/// `nested` is only borrowed, so it stays usable afterwards, but can't be moved or
+/// mutated while the returned iterator is alive.
+pub fn flatten_ref<'a, C>(nested: &'a C) -> Flatten<<&'a C as IntoIterator>::IntoIter>
+where
+    C: ?Sized,
+    &'a C: IntoIterator,
+    <&'a C as IntoIterator>::Item: IntoIterator,
+{
+    flatten(nested)
+}
+
+/// Flattens a `Vec` of `Vec`s into one `Vec`, moving each inner `Vec` in bulk instead of
+/// item by item as `flatten(nested).collect()` does.
+pub fn flatten_collect<T>(nested: Vec<Vec<T>>) -> Vec<T> {
+    let mut flat = Vec::with_capacity(nested.iter().map(Vec::len).sum());
+    for mut inner in nested {
+        flat.append(&mut inner);
+    }
+    flat
+}
+
 pub struct Flatten<I>
 where
     I: Iterator,
@@ -28,6 +52,52 @@ where
             back_iter: None,
         }
     }
+
+    /// Like `nth`, but leaves in `n` how many items were still to be skipped when the
+    /// flatten ran out, so callers can tell how many items it used up.
+    fn nth_remaining(&mut self, n: &mut usize) -> Option<<I::Item as IntoIterator>::Item> {
+        loop {
+            if let Some(front_iter) = self.front_iter.as_mut() {
+                if let Some(item) = nth_in(front_iter, n) {
+                    return Some(item);
+                }
+                self.front_iter = None;
+            }
+
+            if let Some(next_inner) = self.outer.next() {
+                self.front_iter = Some(next_inner.into_iter());
+            } else {
+                let item = nth_in(self.back_iter.as_mut()?, n);
+                if item.is_none() {
+                    self.back_iter = None;
+                }
+                return item;
+            }
+        }
+    }
+}
+
+impl<I> Flatten<I>
+where
+    I: Iterator + Clone,
+    I::Item: IntoIterator,
+{
+    /// Rebuilds a flatten of `outer` that has already yielded `consumed` items.
+    ///
+    /// The skipped items are produced again and dropped, so this costs as much as
+    /// iterating them. `from_parts(outer, 0)` starts a flatten that can later be
+    /// split again with [`ResumableFlatten::into_parts`].
+    pub fn from_parts(outer: I, consumed: usize) -> ResumableFlatten<I> {
+        let mut flat = Flatten::new(outer.clone());
+        if let Some(n) = consumed.checked_sub(1) {
+            flat.nth(n);
+        }
+        ResumableFlatten {
+            flat,
+            origin: outer,
+            consumed,
+        }
+    }
 }
 
 impl<I> Iterator for Flatten<I>
@@ -53,6 +123,74 @@ where
             }
         }
     }
+
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        self.nth_remaining(&mut n)
+    }
+
+    fn find<P>(&mut self, mut predicate: P) -> Option<Self::Item>
+    where
+        P: FnMut(&Self::Item) -> bool,
+    {
+        // Overriding `try_fold` needs the unstable `Try` trait, so `find` is overridden
+        // instead, handing the search to each inner iterator's own `find`. An inner iterator
+        // with items left after the match is kept in `front_iter`, so iteration resumes there.
+        if let Some(front_iter) = self.front_iter.as_mut() {
+            if let Some(item) = front_iter.find(&mut predicate) {
+                return Some(item);
+            }
+            self.front_iter = None;
+        }
+        for next_inner in self.outer.by_ref() {
+            let mut front_iter = next_inner.into_iter();
+            if let Some(item) = front_iter.find(&mut predicate) {
+                self.front_iter = Some(front_iter);
+                return Some(item);
+            }
+        }
+        let item = self.back_iter.as_mut()?.find(&mut predicate);
+        if item.is_none() {
+            self.back_iter = None;
+        }
+        item
+    }
+
+    fn last(self) -> Option<Self::Item> {
+        // Stable Rust can't specialize on `I: DoubleEndedIterator`, so instead of walking
+        // every element, only the last element of each inner iterator is asked for,
+        // which lets inner iterators use their own `last`.
+        let front = self.front_iter.and_then(Iterator::last);
+        let middle = self
+            .outer
+            .filter_map(|inner| inner.into_iter().last())
+            .last();
+        let back = self.back_iter.and_then(Iterator::last);
+        back.or(middle).or(front)
+    }
+}
+
+/// Returns the `n`th element of `iter`, or subtracts the number of elements skipped from `n`
+/// if `iter` runs out first.
+///
+/// An iterator whose `size_hint` is exact, as for every `ExactSizeIterator`, is skipped as
+/// a whole without stepping through it; others are advanced element by element.
+fn nth_in<J: Iterator>(iter: &mut J, n: &mut usize) -> Option<J::Item> {
+    match iter.size_hint() {
+        (len, Some(upper)) if len == upper && len <= *n => {
+            *n -= len;
+            None
+        }
+        (len, Some(upper)) if len == upper => iter.nth(std::mem::take(n)),
+        _ => {
+            for item in iter.by_ref() {
+                if *n == 0 {
+                    return Some(item);
+                }
+                *n -= 1;
+            }
+            None
+        }
+    }
 }
 
 impl<I> DoubleEndedIterator for Flatten<I>
@@ -79,10 +217,195 @@ where
     }
 }
 
+/// A flatten created by `Flatten::from_parts`, which counts the items it yields so it can
+/// be split up again and resumed later.
+///
+/// Only iterates from the front, so its position is always a count of items.
+pub struct ResumableFlatten<I>
+where
+    I: Iterator,
+    I::Item: IntoIterator,
+{
+    flat: Flatten<I>,
+    origin: I,
+    consumed: usize,
+}
+
+impl<I> ResumableFlatten<I>
+where
+    I: Iterator + Clone,
+    I::Item: IntoIterator,
+{
+    /// Splits the flatten into its original outer iterator and the number of items it
+    /// has used up, so that `Flatten::from_parts` can resume it.
+    pub fn into_parts(self) -> (I, usize) {
+        (self.origin, self.consumed)
+    }
+}
+
+impl<I> Iterator for ResumableFlatten<I>
+where
+    I: Iterator,
+    I::Item: IntoIterator,
+{
+    type Item = <I::Item as IntoIterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.flat.next()?;
+        self.consumed += 1;
+        Some(item)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let mut remaining = n;
+        let item = self.flat.nth_remaining(&mut remaining);
+        // Running out used up every item that was skipped, not just the ones before a match.
+        self.consumed += match item {
+            Some(_) => n + 1,
+            None => n - remaining,
+        };
+        item
+    }
+
+    fn find<P>(&mut self, mut predicate: P) -> Option<Self::Item>
+    where
+        P: FnMut(&Self::Item) -> bool,
+    {
+        // Every item the predicate sees is used up, so counting calls tracks `consumed`.
+        let consumed = &mut self.consumed;
+        self.flat.find(|item| {
+            *consumed += 1;
+            predicate(item)
+        })
+    }
+}
+
+pub struct Chunks<I> {
+    iter: I,
+    size: usize,
+}
+
+impl<I> Iterator for Chunks<I>
+where
+    I: Iterator,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.iter.by_ref().take(self.size).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+pub struct Windows<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    size: usize,
+    window: std::collections::VecDeque<I::Item>,
+}
+
+impl<I> Iterator for Windows<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some(self.window.iter().cloned().collect())
+    }
+}
+
+pub struct Intersperse<I>
+where
+    I: Iterator,
+{
+    iter: std::iter::Peekable<I>,
+    sep: I::Item,
+    needs_sep: bool,
+}
+
+impl<I> Iterator for Intersperse<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only yield a separator once it is known another item follows it.
+        if self.needs_sep && self.iter.peek().is_some() {
+            self.needs_sep = false;
+            return Some(self.sep.clone());
+        }
+        let item = self.iter.next()?;
+        self.needs_sep = true;
+        Some(item)
+    }
+}
+
+pub struct Dedup<I>
+where
+    I: Iterator,
+{
+    iter: std::iter::Peekable<I>,
+}
+
+impl<I> Iterator for Dedup<I>
+where
+    I: Iterator,
+    I::Item: PartialEq,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        // Skip the rest of the run before handing out its first item.
+        while self.iter.next_if(|next| *next == item).is_some() {}
+        Some(item)
+    }
+}
+
 pub trait IteratorExt: Iterator + Sized {
     fn our_flatten(self) -> Flatten<Self>
     where
         Self::Item: IntoIterator;
+
+    /// Groups items into `Vec`s of `size` elements; the last chunk may be shorter.
+    ///
+    /// Panics if `size` is 0.
+    fn our_chunks(self, size: usize) -> Chunks<Self>;
+
+    /// Yields overlapping windows of `size` consecutive items, like `slice::windows`.
+    /// Nothing is yielded if there are fewer than `size` items.
+    ///
+    /// Panics if `size` is 0.
+    fn our_windows(self, size: usize) -> Windows<Self>
+    where
+        Self::Item: Clone;
+
+    /// Yields `sep` between every two items, with no separator before the first
+    /// or after the last one.
+    fn our_intersperse(self, sep: Self::Item) -> Intersperse<Self>
+    where
+        Self::Item: Clone;
+
+    /// Collapses runs of consecutive equal items into their first item, like `Vec::dedup`.
+    fn our_dedup(self) -> Dedup<Self>
+    where
+        Self::Item: PartialEq;
 }
 
 impl<T> IteratorExt for T
@@ -95,6 +418,43 @@ where
     {
         flatten(self)
     }
+
+    fn our_chunks(self, size: usize) -> Chunks<Self> {
+        assert!(size != 0, "chunk size must be non-zero");
+        Chunks { iter: self, size }
+    }
+
+    fn our_windows(self, size: usize) -> Windows<Self>
+    where
+        Self::Item: Clone,
+    {
+        assert!(size != 0, "window size must be non-zero");
+        Windows {
+            iter: self,
+            size,
+            window: std::collections::VecDeque::with_capacity(size),
+        }
+    }
+
+    fn our_intersperse(self, sep: Self::Item) -> Intersperse<Self>
+    where
+        Self::Item: Clone,
+    {
+        Intersperse {
+            iter: self.peekable(),
+            sep,
+            needs_sep: false,
+        }
+    }
+
+    fn our_dedup(self) -> Dedup<Self>
+    where
+        Self::Item: PartialEq,
+    {
+        Dedup {
+            iter: self.peekable(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -175,4 +535,300 @@ mod tests {
             .collect();
         assert_eq!(result, vec![1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn test_our_chunks() {
+        assert_eq!(
+            (0..7).our_chunks(3).collect::<Vec<_>>(),
+            vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]
+        );
+        assert_eq!((0..6).our_chunks(3).count(), 2);
+    }
+
+    #[test]
+    fn test_our_chunks_empty() {
+        assert!(std::iter::empty::<()>().our_chunks(3).next().is_none());
+    }
+
+    #[test]
+    fn test_our_chunks_after_flatten() {
+        let chunks: Vec<_> = vec![vec![1, 2], vec![3], vec![4, 5]]
+            .into_iter()
+            .our_flatten()
+            .our_chunks(2)
+            .collect();
+        assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_our_chunks_zero_size() {
+        let _ = (0..7).our_chunks(0);
+    }
+
+    #[test]
+    fn test_our_windows() {
+        assert_eq!(
+            vec![1, 2, 3, 4]
+                .into_iter()
+                .our_windows(2)
+                .collect::<Vec<_>>(),
+            vec![vec![1, 2], vec![2, 3], vec![3, 4]]
+        );
+        assert_eq!(
+            (0..3).our_windows(3).collect::<Vec<_>>(),
+            vec![vec![0, 1, 2]]
+        );
+    }
+
+    #[test]
+    fn test_our_windows_larger_than_len() {
+        assert!((0..3).our_windows(4).next().is_none());
+        assert!(std::iter::empty::<()>().our_windows(1).next().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_our_windows_zero_size() {
+        let _ = (0..3).our_windows(0);
+    }
+
+    #[test]
+    fn test_our_intersperse() {
+        assert_eq!(
+            [1, 2, 3].into_iter().our_intersperse(0).collect::<Vec<_>>(),
+            vec![1, 0, 2, 0, 3]
+        );
+        assert_eq!(
+            std::iter::once(1).our_intersperse(0).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert!(std::iter::empty().our_intersperse(0).next().is_none());
+    }
+
+    #[test]
+    fn test_our_intersperse_after_our_flatten() {
+        let words = vec![vec!["a", "b"], vec![], vec!["c"]];
+        assert_eq!(
+            words
+                .into_iter()
+                .our_flatten()
+                .our_intersperse(",")
+                .collect::<String>(),
+            "a,b,c"
+        );
+    }
+
+    #[test]
+    fn test_our_dedup() {
+        assert_eq!(
+            [1, 1, 2, 3, 3, 3, 1]
+                .into_iter()
+                .our_dedup()
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 1]
+        );
+        assert!(std::iter::empty::<()>().our_dedup().next().is_none());
+        assert_eq!(std::iter::repeat_n('a', 5).our_dedup().count(), 1);
+    }
+
+    #[test]
+    fn test_our_dedup_after_our_flatten() {
+        let groups = vec![vec![1, 2, 2], vec![2, 3], vec![], vec![3]];
+        assert_eq!(
+            groups
+                .into_iter()
+                .our_flatten()
+                .our_dedup()
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_flatten_collect() {
+        let nested = vec![vec![1, 2], vec![], vec![3], vec![4, 5, 6]];
+        let flat = flatten_collect(nested.clone());
+        assert_eq!(flat, flatten(nested).collect::<Vec<_>>());
+        assert_eq!(flat.capacity(), 6);
+        assert!(flatten_collect(Vec::<Vec<()>>::new()).is_empty());
+    }
+
+    #[test]
+    #[ignore = "compares timings, run with `--release --ignored --nocapture`"]
+    fn test_flatten_collect_is_faster_than_iterating() {
+        use std::time::Instant;
+
+        let nested: Vec<Vec<u64>> = (0..10_000).map(|i| (0..i % 100).collect()).collect();
+
+        let start = Instant::now();
+        let iterated: Vec<_> = flatten(nested.clone()).collect();
+        let iterated_time = start.elapsed();
+        let start = Instant::now();
+        let collected = flatten_collect(nested);
+        let collected_time = start.elapsed();
+
+        eprintln!("flatten().collect(): {iterated_time:?}, flatten_collect(): {collected_time:?}");
+        assert_eq!(iterated, collected);
+    }
+
+    #[test]
+    fn test_find_resumes_after_match() {
+        let mut iter = flatten(vec![vec![1, 2], vec![3, 4]]);
+        assert_eq!(iter.find(|&x| x == 3), Some(3));
+        assert_eq!(iter.next(), Some(4));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_find_with_buffered_front_and_back() {
+        let mut iter = flatten(vec![vec![1, 2], vec![3], vec![], vec![4, 5, 6]]);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(6));
+        assert_eq!(iter.find(|&x| x > 3), Some(4));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.find(|_| true), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_find_uses_inner_find() {
+        // An inner iterator that can only be searched with `find`, to check the
+        // search is handed over instead of stepping through `next`.
+        struct FindOnly(std::vec::IntoIter<i32>);
+        impl Iterator for FindOnly {
+            type Item = i32;
+            fn next(&mut self) -> Option<i32> {
+                panic!("next called instead of find")
+            }
+            fn find<P: FnMut(&i32) -> bool>(&mut self, predicate: P) -> Option<i32> {
+                self.0.find(predicate)
+            }
+        }
+
+        let outer = vec![vec![1, 2], vec![3, 4]]
+            .into_iter()
+            .map(|inner| FindOnly(inner.into_iter()));
+        assert_eq!(flatten(outer).find(|&x| x == 3), Some(3));
+    }
+
+    #[test]
+    fn test_last() {
+        assert_eq!(flatten(vec![vec![1, 2], vec![3, 4]]).last(), Some(4));
+        assert_eq!(
+            flatten(vec![vec![1, 2], vec![3, 4], vec![]]).last(),
+            Some(4)
+        );
+        assert_eq!(flatten::<Vec<Vec<()>>>(vec![vec![], vec![]]).last(), None);
+    }
+
+    #[test]
+    fn test_last_with_buffered_front_and_back() {
+        let mut iter = flatten(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(6));
+        assert_eq!(iter.last(), Some(5));
+
+        let mut iter = flatten(vec![vec![1, 2, 3], vec![4]]);
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.last(), Some(3));
+    }
+
+    #[test]
+    fn test_nth() {
+        let mut iter = flatten(vec![vec![0, 1, 2], vec![3, 4, 5]]);
+        assert_eq!(iter.nth(4), Some(4));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.next(), None);
+
+        let mut iter = flatten(vec![vec![0, 1, 2], vec![], vec![3, 4, 5]]);
+        assert_eq!(iter.nth(1), Some(1));
+        assert_eq!(iter.nth(1), Some(3));
+        assert_eq!(iter.nth(5), None);
+    }
+
+    #[test]
+    fn test_nth_with_unknown_inner_lengths() {
+        let mut iter = flatten((0..3).map(|i| (0..10).filter(move |j| j % 3 == i)));
+        // Yields 0, 3, 6, 9, 1, 4, 7, 2, 5, 8
+        assert_eq!(iter.nth(5), Some(4));
+        assert_eq!(iter.next(), Some(7));
+        assert_eq!(iter.count(), 3);
+    }
+
+    #[test]
+    fn test_nth_reaches_into_back_iter() {
+        let mut iter = flatten(vec![vec![0, 1], vec![2, 3, 4]]);
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.nth(2), Some(2));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_flatten_ref() {
+        let nested = vec![vec![1, 2], vec![], vec![3]];
+        let items: Vec<&i32> = flatten_ref(&nested).collect();
+        assert_eq!(items, [&1, &2, &3]);
+        let reversed: Vec<&i32> = flatten_ref(&nested).rev().collect();
+        assert_eq!(reversed, [&3, &2, &1]);
+        // `nested` was only borrowed.
+        assert_eq!(nested.len(), 3);
+    }
+
+    #[test]
+    fn test_flatten_ref_both_ends() {
+        let nested = vec![vec![1, 2, 3], vec![4, 5]];
+        let mut iter = flatten_ref(&nested);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.copied().collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_flatten_ref_slice() {
+        let nested: &[Vec<i32>] = &[vec![1], vec![2, 3]];
+        assert_eq!(flatten_ref(nested).sum::<i32>(), 6);
+    }
+
+    #[test]
+    fn test_into_parts_and_from_parts() {
+        let outer = vec![vec![1, 2, 3], vec![], vec![4, 5], vec![6]].into_iter();
+        let mut first_half = Flatten::from_parts(outer, 0);
+        let mut seen: Vec<_> = first_half.by_ref().take(3).collect();
+
+        let (outer, consumed) = first_half.into_parts();
+        assert_eq!(consumed, 3);
+        seen.extend(Flatten::from_parts(outer, consumed));
+        assert_eq!(seen, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_into_parts_counts_nth_and_find() {
+        let outer = vec![vec![1, 2, 3], vec![4, 5], vec![6, 7]].into_iter();
+        let mut iter = Flatten::from_parts(outer, 0);
+        assert_eq!(iter.nth(1), Some(2));
+        assert_eq!(iter.find(|&x| x == 5), Some(5));
+
+        let (outer, consumed) = iter.into_parts();
+        assert_eq!(consumed, 5);
+        assert_eq!(
+            Flatten::from_parts(outer, consumed).collect::<Vec<_>>(),
+            vec![6, 7]
+        );
+    }
+
+    #[test]
+    fn test_into_parts_after_nth_runs_out() {
+        let outer = vec![vec![1, 2], vec![3]].into_iter();
+        let mut iter = Flatten::from_parts(outer, 0);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.nth(5), None);
+
+        let (outer, consumed) = iter.into_parts();
+        assert_eq!(consumed, 3);
+        assert_eq!(Flatten::from_parts(outer, consumed).next(), None);
+    }
 }
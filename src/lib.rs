@@ -1,7 +1,22 @@
+pub mod atomic_refcell;
 pub mod atomics;
+pub mod buffer_pool;
 pub mod cell;
+pub mod cell_stack;
 pub mod channel;
+pub mod defer;
+pub mod event_bus;
+pub mod flags;
 pub mod flatten;
+pub mod memoized;
+pub mod mpmc;
+pub mod parallel;
+pub mod pipeline;
 pub mod rc;
+pub mod recursion_guard;
 pub mod refcell;
+pub mod seq_lock;
+pub mod small_channel;
+pub mod spsc;
 pub mod vec_macro;
+pub mod wait_group;
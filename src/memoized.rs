@@ -0,0 +1,80 @@
+use crate::refcell::RefCell;
+
+/// A value that is computed on first access and cached afterwards.
+pub struct Memoized<T> {
+    value: RefCell<Option<T>>,
+}
+
+impl<T> Memoized<T> {
+    pub fn new() -> Self {
+        Memoized {
+            value: RefCell::new(None),
+        }
+    }
+
+    /// Returns the cached value, computing it with `f` if this is the first access.
+    ///
+    /// Panics if `f` itself tries to initialize the same `Memoized`.
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        let initialized = self
+            .value
+            .borrow()
+            .expect("Memoized is only borrowed mutably while storing the value")
+            .is_some();
+        if !initialized {
+            let value = f();
+            let mut slot = self
+                .value
+                .borrow_mut()
+                .expect("Memoized is not borrowed while computing the value");
+            assert!(slot.is_none(), "reentrant init of Memoized");
+            *slot = Some(value);
+        }
+
+        let slot = self
+            .value
+            .borrow()
+            .expect("Memoized is only borrowed mutably while storing the value");
+        let value: *const T = slot.as_ref().expect("value was initialized above");
+        // SAFETY: once the value is stored it is never replaced, taken or dropped before
+        // `self`, because no method mutates it after initialization. The value lives inside
+        // `self`, which cannot move while it is borrowed, so the pointer stays valid for
+        // the lifetime of `&self` even after the `Ref` guard is released.
+        unsafe { &*value }
+    }
+}
+
+impl<T> Default for Memoized<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn test_get_or_init_runs_init_once() {
+        let calls = Cell::new(0);
+        let memoized = Memoized::new();
+        let init = || {
+            calls.set(calls.get() + 1);
+            String::from("Hello")
+        };
+        let first = memoized.get_or_init(init);
+        let second = memoized.get_or_init(init);
+        assert_eq!(first, "Hello");
+        assert_eq!(second, "Hello");
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_or_init_reentrant() {
+        let memoized = Memoized::new();
+        memoized.get_or_init(|| *memoized.get_or_init(|| 37) + 1);
+    }
+}
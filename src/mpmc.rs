@@ -0,0 +1,481 @@
+use crate::channel::SendError;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// The error returned by `Sender::try_send`, giving back the message that wasn't sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full, but receivers remain.
+    Full(T),
+    /// All receivers are gone.
+    Disconnected(T),
+}
+
+// Like `SendError`, doesn't require `T: Debug`.
+impl<T> std::fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> std::fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("sending on a full channel"),
+            TrySendError::Disconnected(_) => f.write_str("sending on a closed channel"),
+        }
+    }
+}
+
+impl<T> std::error::Error for TrySendError<T> {}
+
+/// The error returned by `Sender::send_timeout`, giving back the message that wasn't sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    /// The channel stayed full for the whole timeout.
+    Timeout(T),
+    /// All receivers are gone.
+    Disconnected(T),
+}
+
+impl<T> std::fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => f.write_str("Timeout(..)"),
+            SendTimeoutError::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> std::fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => f.write_str("timed out sending on a full channel"),
+            SendTimeoutError::Disconnected(_) => f.write_str("sending on a closed channel"),
+        }
+    }
+}
+
+impl<T> std::error::Error for SendTimeoutError<T> {}
+
+struct Inner<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    senders: usize,
+    receivers: usize,
+}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+/// The sending half of a bounded multi-producer multi-consumer channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `t`, blocking while the channel is full.
+    ///
+    /// Gives `t` back if all receivers are gone, also when they go away while waiting.
+    pub fn send(&mut self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        loop {
+            if inner.receivers == 0 {
+                return Err(SendError(t));
+            }
+            if inner.queue.len() < inner.capacity {
+                break;
+            }
+            inner = self.shared.not_full.wait(inner).unwrap();
+        }
+        inner.queue.push_back(t);
+        drop(inner);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Sends `t`, blocking for at most `timeout` while the channel is full.
+    ///
+    /// Waits against a deadline, so spurious wakeups don't extend the timeout. A timeout
+    /// too large to express as a deadline blocks like `send`.
+    pub fn send_timeout(&mut self, t: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = std::time::Instant::now().checked_add(timeout);
+        let mut inner = self.shared.inner.lock().unwrap();
+        loop {
+            if inner.receivers == 0 {
+                return Err(SendTimeoutError::Disconnected(t));
+            }
+            if inner.queue.len() < inner.capacity {
+                break;
+            }
+            let Some(deadline) = deadline else {
+                inner = self.shared.not_full.wait(inner).unwrap();
+                continue;
+            };
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return Err(SendTimeoutError::Timeout(t));
+            }
+            inner = self
+                .shared
+                .not_full
+                .wait_timeout(inner, deadline - now)
+                .unwrap()
+                .0;
+        }
+        inner.queue.push_back(t);
+        drop(inner);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Sends `t` if the channel has room, without blocking.
+    pub fn try_send(&mut self, t: T) -> Result<(), TrySendError<T>> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if inner.receivers == 0 {
+            return Err(TrySendError::Disconnected(t));
+        }
+        if inner.queue.len() == inner.capacity {
+            return Err(TrySendError::Full(t));
+        }
+        inner.queue.push_back(t);
+        drop(inner);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Tries to send `t` up to `attempts` times, sleeping `backoff` between attempts
+    /// while the channel is full.
+    ///
+    /// Gives `t` back once all attempts found the channel full, or right away if all
+    /// receivers are gone.
+    pub fn send_with_retries(
+        &mut self,
+        mut t: T,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<(), SendError<T>> {
+        for attempt in 0..attempts {
+            if attempt != 0 {
+                std::thread::sleep(backoff);
+            }
+            match self.try_send(t) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(back)) => t = back,
+                Err(TrySendError::Disconnected(back)) => return Err(SendError(back)),
+            }
+        }
+        Err(SendError(t))
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.inner.lock().unwrap().senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        inner.senders -= 1;
+        let was_last = inner.senders == 0;
+        drop(inner);
+
+        if was_last {
+            // Every waiting receiver has to see the disconnection, not just one.
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+/// The receiving half of a bounded multi-producer multi-consumer channel.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives a message, blocking while the channel is empty.
+    ///
+    /// Returns `None` once the channel is empty and all senders are gone.
+    pub fn receive(&mut self) -> Option<T> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        loop {
+            if let Some(t) = inner.queue.pop_front() {
+                drop(inner);
+                self.shared.not_full.notify_one();
+                return Some(t);
+            }
+            if inner.senders == 0 {
+                return None;
+            }
+            inner = self.shared.not_empty.wait(inner).unwrap();
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.inner.lock().unwrap().receivers += 1;
+        Receiver {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        inner.receivers -= 1;
+        let was_last = inner.receivers == 0;
+        drop(inner);
+
+        if was_last {
+            // Every sender blocked on a full channel has to see the disconnection.
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receive()
+    }
+}
+
+/// Creates a channel holding at most `capacity` messages, whose senders and receivers
+/// can both be cloned.
+///
+/// Sends block while the channel is full and receives while it is empty. Both sides
+/// detect when the other one is gone. Panics if `capacity` is 0.
+///
+/// The queue grows as messages arrive instead of allocating `capacity` up front, so a
+/// large bound used as a soft limit costs nothing until the channel fills.
+pub fn mpmc_bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity != 0, "capacity must be non-zero");
+    let inner = Inner {
+        queue: VecDeque::new(),
+        capacity,
+        senders: 1,
+        receivers: 1,
+    };
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(inner),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_send_and_receive() {
+        let (mut tx, mut rx) = mpmc_bounded(2);
+        tx.send(37).unwrap();
+        tx.send(73).unwrap();
+        assert_eq!(rx.receive(), Some(37));
+        assert_eq!(rx.receive(), Some(73));
+        drop(tx);
+        assert_eq!(rx.receive(), None);
+    }
+
+    #[test]
+    fn test_send_blocks_while_full() {
+        let (mut tx, mut rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || {
+            tx.send(2).unwrap();
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        assert_eq!(rx.receive(), Some(1));
+        handle.join().unwrap();
+        assert_eq!(rx.receive(), Some(2));
+    }
+
+    #[test]
+    fn test_blocked_send_fails_when_receivers_are_gone() {
+        let (mut tx, rx) = mpmc_bounded(1);
+        let rx_clone = rx.clone();
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || tx.send(2));
+        thread::sleep(Duration::from_millis(50));
+        drop(rx);
+        drop(rx_clone);
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+    }
+
+    #[test]
+    fn test_every_blocked_receiver_sees_disconnection() {
+        let (tx, rx) = mpmc_bounded::<i32>(1);
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let mut rx = rx.clone();
+                thread::spawn(move || rx.receive())
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(50));
+        drop(tx.clone());
+        drop(tx);
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_large_capacity_doesnt_preallocate() {
+        let (mut tx, mut rx) = mpmc_bounded(usize::MAX / 2);
+        tx.send(37).unwrap();
+        assert_eq!(rx.receive(), Some(37));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be non-zero")]
+    fn test_zero_capacity() {
+        let _ = mpmc_bounded::<i32>(0);
+    }
+
+    #[test]
+    fn test_many_producers_and_consumers() {
+        let (tx, rx) = mpmc_bounded(16);
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let mut tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..2500 {
+                        tx.send(p * 2500 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || rx.collect::<Vec<_>>())
+            })
+            .collect();
+        drop(rx);
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let mut received: Vec<_> = consumers
+            .into_iter()
+            .flat_map(|consumer| consumer.join().unwrap())
+            .collect();
+        received.sort_unstable();
+        assert_eq!(received, (0..10_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_try_send() {
+        let (mut tx, mut rx) = mpmc_bounded(1);
+        assert_eq!(tx.try_send(1), Ok(()));
+        assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+        assert_eq!(rx.receive(), Some(1));
+        assert_eq!(tx.try_send(3), Ok(()));
+        drop(rx);
+        assert_eq!(tx.try_send(4), Err(TrySendError::Disconnected(4)));
+    }
+
+    #[test]
+    fn test_send_with_retries_gives_up_on_stalled_consumer() {
+        use std::time::Instant;
+
+        let (mut tx, _rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let start = Instant::now();
+        assert_eq!(
+            tx.send_with_retries(2, 3, Duration::from_millis(10)),
+            Err(SendError(2))
+        );
+        // Sleeps between the three attempts, not after the last one.
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_send_with_retries_succeeds_once_room_frees_up() {
+        let (mut tx, mut rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let consumer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            (rx.receive(), rx.receive())
+        });
+        assert_eq!(
+            tx.send_with_retries(2, 100, Duration::from_millis(5)),
+            Ok(())
+        );
+        assert_eq!(consumer.join().unwrap(), (Some(1), Some(2)));
+    }
+
+    #[test]
+    fn test_send_with_retries_stops_when_disconnected() {
+        let (mut tx, rx) = mpmc_bounded(1);
+        drop(rx);
+        assert_eq!(
+            tx.send_with_retries(1, 1000, Duration::from_secs(1)),
+            Err(SendError(1))
+        );
+    }
+
+    #[test]
+    fn test_send_timeout_times_out_while_full() {
+        use std::time::Instant;
+
+        let (mut tx, _rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let start = Instant::now();
+        assert_eq!(
+            tx.send_timeout(2, Duration::from_millis(50)),
+            Err(SendTimeoutError::Timeout(2))
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_send_timeout_sends_once_room_frees_up() {
+        let (mut tx, mut rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let consumer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            (rx.receive(), rx.receive())
+        });
+        assert_eq!(tx.send_timeout(2, Duration::from_secs(10)), Ok(()));
+        assert_eq!(consumer.join().unwrap(), (Some(1), Some(2)));
+    }
+
+    #[test]
+    fn test_send_timeout_disconnected() {
+        let (mut tx, rx) = mpmc_bounded(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || tx.send_timeout(2, Duration::from_secs(10)));
+        thread::sleep(Duration::from_millis(20));
+        drop(rx);
+        assert_eq!(
+            handle.join().unwrap(),
+            Err(SendTimeoutError::Disconnected(2))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::channel::channel;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+/// Maps `items` through `f` on `workers` threads and collects the results.
+///
+/// Each worker gets its own clone of the `Sender` and the calling thread gathers the
+/// results from the `Receiver`, so they come back in completion order, not input order.
+/// All workers are joined before returning.
+///
+/// Panics if `workers` is 0.
+pub fn parallel_map<T, R>(items: Vec<T>, workers: usize, f: impl Fn(T) -> R + Sync) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+{
+    assert!(workers != 0, "parallel_map needs at least one worker");
+    let mut batches: Vec<Vec<T>> = (0..workers).map(|_| Vec::new()).collect();
+    for (i, item) in items.into_iter().enumerate() {
+        batches[i % workers].push(item);
+    }
+
+    let (tx, rx) = channel();
+    let f = &f;
+    thread::scope(|scope| {
+        for batch in batches {
+            let mut tx = tx.clone();
+            scope.spawn(move || {
+                for item in batch {
+                    tx.send(f(item)).expect("the receiver outlives the workers");
+                }
+            });
+        }
+        // The receiver ends once the last worker drops its sender.
+        drop(tx);
+        rx.collect()
+    })
+}
+
+/// Maps `items` through `f` on `workers` threads, returning the results in input order.
+///
+/// Each item is sent to `parallel_map` tagged with its index, and the results are put
+/// back in order by that index once they have all arrived.
+///
+/// Panics if `workers` is 0.
+pub fn ordered_parallel_map<T, R>(
+    items: Vec<T>,
+    workers: usize,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+{
+    let tagged = items.into_iter().enumerate().collect();
+    let mut results = parallel_map(tagged, workers, |(index, item)| (index, f(item)));
+    results.sort_unstable_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Maps `items` through `f`, running at most `max_in_flight` calls of `f` at the same time.
+///
+/// Every item gets its own thread, but a thread is only spawned once a semaphore permit
+/// is free, so no more than `max_in_flight` threads exist at once. Results are collected
+/// through the channel in completion order.
+///
+/// Panics if `max_in_flight` is 0.
+pub fn parallel_map_bounded<T, R>(
+    items: Vec<T>,
+    max_in_flight: usize,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+{
+    assert!(
+        max_in_flight != 0,
+        "parallel_map_bounded needs at least one permit"
+    );
+    let semaphore = Semaphore::new(max_in_flight);
+    let (tx, rx) = channel();
+    let f = &f;
+    let semaphore = &semaphore;
+    thread::scope(|scope| {
+        let spawner = scope.spawn(move || {
+            for item in items {
+                let permit = semaphore.acquire();
+                let mut tx = tx.clone();
+                scope.spawn(move || {
+                    tx.send(f(item)).expect("the receiver outlives the workers");
+                    drop(permit);
+                });
+            }
+        });
+        // Collect while the spawner is still waiting for permits.
+        let results = rx.collect();
+        spawner.join().unwrap();
+        results
+    })
+}
+
+/// A counting semaphore limiting how many tasks run at once.
+struct Semaphore {
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Semaphore {
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a permit is free. The permit is given back when dropped.
+    fn acquire(&self) -> Permit<'_> {
+        let mut permits = self.permits.lock().unwrap();
+        while *permits == 0 {
+            permits = self.released.wait(permits).unwrap();
+        }
+        *permits -= 1;
+        Permit { semaphore: self }
+    }
+}
+
+struct Permit<'semaphore> {
+    semaphore: &'semaphore Semaphore,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.permits.lock().unwrap() += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_map() {
+        let mut results = parallel_map((0..100).collect(), 4, |i| i * 2);
+        results.sort_unstable();
+        assert_eq!(results, (0..100).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parallel_map_more_workers_than_items() {
+        let mut results = parallel_map(vec!["a", "b"], 8, str::to_uppercase);
+        results.sort_unstable();
+        assert_eq!(results, ["A", "B"]);
+    }
+
+    #[test]
+    fn test_parallel_map_empty() {
+        assert!(parallel_map(Vec::<i32>::new(), 4, |i| i).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parallel_map_without_workers() {
+        parallel_map(vec![1], 0, |i| i);
+    }
+
+    #[test]
+    fn test_ordered_parallel_map_keeps_input_order() {
+        let f = |i: u64| {
+            // Later items finish first, so completion order is roughly reversed.
+            thread::sleep(std::time::Duration::from_micros((100 - i) * 50));
+            i * 3
+        };
+        let results = ordered_parallel_map((0..100).collect(), 4, f);
+        assert_eq!(results, (0..100).map(f).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parallel_map_bounded_limits_tasks_in_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let in_flight = AtomicUsize::new(0);
+        let max_seen = AtomicUsize::new(0);
+        let mut results = parallel_map_bounded((0..20).collect(), 3, |i| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_seen.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(std::time::Duration::from_millis(5));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            i * 2
+        });
+        results.sort_unstable();
+        assert_eq!(results, (0..20).map(|i| i * 2).collect::<Vec<_>>());
+        assert!(max_seen.load(Ordering::SeqCst) <= 3);
+        assert!(max_seen.load(Ordering::SeqCst) >= 1);
+    }
+
+    #[test]
+    fn test_parallel_map_bounded_empty() {
+        assert!(parallel_map_bounded(Vec::<i32>::new(), 2, |i| i).is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::mpmc::{mpmc_bounded, Receiver};
+use std::thread;
+
+// Connects the stages added so far to the receiver of their input, using channels of
+// the given capacity, and returns the receiver of the last stage's output.
+type Connect<In, Out> = Box<dyn FnOnce(Receiver<In>, usize) -> Receiver<Out>>;
+
+/// Chains processing stages, each running on its own thread, connected by bounded channels.
+///
+/// A stage that falls behind fills the channel in front of it, which blocks the stage
+/// before it, so a slow stage slows down everything upstream instead of letting queues
+/// grow without bound.
+///
+/// ```
+/// use rust_exploration::pipeline::Pipeline;
+///
+/// let output = Pipeline::new()
+///     .stage(|i: i32| i * 2)
+///     .stage(|i| i.to_string())
+///     .run(1..=3);
+/// assert_eq!(output.collect::<Vec<_>>(), ["2", "4", "6"]);
+/// ```
+pub struct Pipeline<In, Out> {
+    capacity: usize,
+    connect: Connect<In, Out>,
+}
+
+impl<T: Send + 'static> Pipeline<T, T> {
+    /// Starts a pipeline without stages, whose channels hold up to 16 messages.
+    pub fn new() -> Self {
+        Pipeline {
+            capacity: 16,
+            connect: Box::new(|input, _| input),
+        }
+    }
+}
+
+impl<T: Send + 'static> Default for Pipeline<T, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<In, Out> Pipeline<In, Out>
+where
+    In: Send + 'static,
+    Out: Send + 'static,
+{
+    /// Sets how many messages every channel between two stages holds.
+    ///
+    /// Panics in `run` if `capacity` is 0.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Adds a stage passing every message through `f` on a thread of its own.
+    pub fn stage<U>(self, mut f: impl FnMut(Out) -> U + Send + 'static) -> Pipeline<In, U>
+    where
+        U: Send + 'static,
+    {
+        let connect = self.connect;
+        Pipeline {
+            capacity: self.capacity,
+            connect: Box::new(move |input, capacity| {
+                let upstream = connect(input, capacity);
+                let (mut tx, rx) = mpmc_bounded(capacity);
+                thread::spawn(move || {
+                    for message in upstream {
+                        // Stop once the output is dropped, which in turn stops the stages
+                        // upstream as their sends fail.
+                        if tx.send(f(message)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                rx
+            }),
+        }
+    }
+
+    /// Starts every stage and feeds `input` into the first one from another thread.
+    ///
+    /// Messages come out of the returned receiver in input order, and it ends once all
+    /// of `input` went through the pipeline.
+    pub fn run<I>(self, input: I) -> Receiver<Out>
+    where
+        I: IntoIterator<Item = In>,
+        I::IntoIter: Send + 'static,
+    {
+        let (mut tx, rx) = mpmc_bounded(self.capacity);
+        let input = input.into_iter();
+        thread::spawn(move || {
+            for message in input {
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        (self.connect)(rx, self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_two_stages_keep_order() {
+        let output = Pipeline::new()
+            .stage(|i: u32| i * 2)
+            .stage(|i| i.to_string())
+            .run(0..100);
+        let expected: Vec<_> = (0..100).map(|i| (i * 2).to_string()).collect();
+        assert_eq!(output.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_without_stages() {
+        let output = Pipeline::new().run(vec!['a', 'b']);
+        assert_eq!(output.collect::<String>(), "ab");
+    }
+
+    #[test]
+    fn test_slow_stage_bounds_upstream_queue() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let consumed = Arc::new(AtomicUsize::new(0));
+        let max_ahead = Arc::new(AtomicUsize::new(0));
+        let (p, c, m) = (produced.clone(), consumed.clone(), max_ahead.clone());
+
+        let output = Pipeline::new()
+            .capacity(2)
+            .stage(move |i: u32| {
+                p.fetch_add(1, Ordering::SeqCst);
+                i * 2
+            })
+            .stage(move |i| {
+                let seen = c.fetch_add(1, Ordering::SeqCst) + 1;
+                let ahead = produced.load(Ordering::SeqCst) - seen;
+                m.fetch_max(ahead, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(2));
+                i.to_string()
+            })
+            .run(0..50);
+        assert_eq!(output.count(), 50);
+
+        // Between the stages, 2 messages wait in the channel and 1 more can be held by
+        // the first stage while its send blocks.
+        assert!(max_ahead.load(Ordering::SeqCst) <= 3);
+        assert_eq!(consumed.load(Ordering::SeqCst), 50);
+    }
+}
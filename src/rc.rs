@@ -1,12 +1,44 @@
 use crate::cell::Cell;
+use crate::refcell::{BorrowError, RefCell};
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::ptr::NonNull;
 
 struct Shared<T> {
     value: T,
-    ref_count: Cell<usize>,
+    // Never zero while the `Shared` is alive: the last `Rc` frees it instead of storing 0.
+    ref_count: Cell<NonZeroUsize>,
 }
 
+/// A single-threaded reference-counted pointer.
+///
+/// `Rc<T>` is covariant in `T`, like `std::rc::Rc`, because both `NonNull<Shared<T>>`
+/// and `PhantomData<Shared<T>>` are. The `PhantomData` tells drop check that dropping
+/// an `Rc<T>` may drop a `T`, so an `Rc` holding a reference can't outlive the value:
+///
+/// ```compile_fail
+/// use rust_exploration::rc::Rc;
+///
+/// let rc;
+/// {
+///     let value = String::from("Hello");
+///     rc = Rc::new(&value);
+/// }
+/// println!("{}", *rc);
+/// ```
+///
+/// Without the unstable `#[may_dangle]` that std uses, this is stricter than std:
+/// the referenced value must outlive the `Rc` even if the `Rc` is never used again.
+///
+/// ```compile_fail
+/// use rust_exploration::rc::Rc;
+///
+/// let _rc;
+/// {
+///     let value = String::from("Hello");
+///     _rc = Rc::new(&value);
+/// }
+/// ```
 pub struct Rc<T> {
     shared: NonNull<Shared<T>>,
     _marker: PhantomData<Shared<T>>,
@@ -16,7 +48,7 @@ impl<T> Rc<T> {
     pub fn new(value: T) -> Self {
         let shared = Box::new(Shared {
             value,
-            ref_count: Cell::new(1),
+            ref_count: Cell::new(NonZeroUsize::MIN),
         });
 
         Rc {
@@ -26,10 +58,139 @@ impl<T> Rc<T> {
         }
     }
 
+    /// Like `new`, but gives the value back instead of aborting if the allocation fails.
+    ///
+    /// `Box::try_new` is unstable, so this allocates through `std::alloc::alloc`, which
+    /// reports failure with a null pointer instead of calling the allocation error handler.
+    pub fn try_new(value: T) -> Result<Self, T> {
+        let layout = std::alloc::Layout::new::<Shared<T>>();
+        // SAFETY: `Shared<T>` contains the non zero-sized ref count, so `layout` is never zero-sized.
+        let Some(shared) = NonNull::new(unsafe { std::alloc::alloc(layout) }.cast::<Shared<T>>())
+        else {
+            return Err(value);
+        };
+        // SAFETY: the pointer is freshly allocated with the layout of `Shared<T>`, so it is
+        // valid and aligned for the write. The global allocator and the same layout are what
+        // `Box` uses, so `Drop` and `try_unwrap` can free it with `Box::from_raw`.
+        unsafe {
+            shared.as_ptr().write(Shared {
+                value,
+                ref_count: Cell::new(NonZeroUsize::MIN),
+            })
+        };
+        Ok(Rc {
+            shared,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Runs `f` with a reference to the inner value and returns its result.
+    pub fn with<R>(this: &Rc<T>, f: impl FnOnce(&T) -> R) -> R {
+        f(this)
+    }
+
+    /// Returns `n` clones of `this`, raising the count once for all of them instead of
+    /// once per clone.
+    pub fn clone_n(this: &Rc<T>, n: usize) -> Vec<Rc<T>> {
+        // SAFETY: `this.shared` is of type `NonNull<Shared<T>>`. The pointer is never null.
+        let shared = unsafe { this.shared.as_ref() };
+        let count = shared.ref_count.get().checked_add(n);
+        shared
+            .ref_count
+            .set(count.expect("reference count overflowed"));
+        // Each `Rc` built here owns one of the counts added above.
+        (0..n)
+            .map(|_| Rc {
+                shared: this.shared,
+                _marker: PhantomData,
+            })
+            .collect()
+    }
+
+    /// Returns the inner value if this is the only `Rc`, otherwise gives the `Rc` back.
+    ///
+    /// `impl<T> TryFrom<Rc<T>> for T` is not allowed by the orphan rules, so this is
+    /// the way to reclaim an owned value.
+    pub fn try_unwrap(this: Rc<T>) -> Result<T, Rc<T>> {
+        if this.ref_count() != 1 {
+            return Err(this);
+        }
+        // SAFETY: This is the only Rc, there are no other references to `Shared` and
+        // forgetting `this` ensures `Drop` doesn't free it a second time.
+        let shared = unsafe { Box::from_raw(this.shared.as_ptr()) };
+        std::mem::forget(this);
+        Ok(shared.value)
+    }
+
+    /// Returns the inner value, moving it out if this is the only `Rc` and cloning it otherwise.
+    pub fn unwrap_or_clone(this: Rc<T>) -> T
+    where
+        T: Clone,
+    {
+        Rc::try_unwrap(this).unwrap_or_else(|rc| (*rc).clone())
+    }
+
+    /// Consumes the `Rc` without decrementing the count, so the value is never dropped
+    /// and its allocation is never freed, and returns a reference that lives forever.
+    ///
+    /// Other `Rc`s to the same value keep working, their count just never reaches zero.
+    pub fn leak(this: Rc<T>) -> &'static T
+    where
+        T: 'static,
+    {
+        // SAFETY: forgetting `this` keeps its count, so the allocation is never freed
+        // and the value is never dropped. `T: 'static`, so nothing it borrows can expire.
+        let value = unsafe { &(*this.shared.as_ptr()).value };
+        std::mem::forget(this);
+        value
+    }
+
     // Method to get the reference count for testing purposes.
     #[allow(dead_code)]
     fn ref_count(&self) -> usize {
-        unsafe { self.shared.as_ref().ref_count.get() }
+        unsafe { self.shared.as_ref().ref_count.get().get() }
+    }
+
+    /// Prints the current strong count and a backtrace of the caller to stderr,
+    /// to track down leaks caused by unexpected clones.
+    ///
+    /// Compiles to nothing unless the `trace_rc` feature is enabled.
+    #[cfg(feature = "trace_rc")]
+    pub fn debug_ref_count(this: &Rc<T>) {
+        eprintln!("{}", Rc::ref_count_trace(this));
+    }
+
+    #[cfg(not(feature = "trace_rc"))]
+    #[inline(always)]
+    pub fn debug_ref_count(_this: &Rc<T>) {}
+
+    #[cfg(feature = "trace_rc")]
+    fn ref_count_trace(this: &Rc<T>) -> String {
+        format!(
+            "Rc strong count: {}\n{}",
+            this.ref_count(),
+            std::backtrace::Backtrace::force_capture()
+        )
+    }
+}
+
+impl<T> Rc<RefCell<T>> {
+    /// Borrows the shared `RefCell` for the duration of `f` and returns its result.
+    ///
+    /// Unlike `RefCell::with_borrow`, which `rc.with_borrow(f)` resolves to, returns
+    /// `BorrowError` instead of panicking if the value is mutably borrowed.
+    pub fn with_borrow<R>(
+        this: &Rc<RefCell<T>>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, BorrowError> {
+        let value = this.borrow().ok_or(BorrowError)?;
+        Ok(f(&value))
+    }
+}
+
+impl<T> From<T> for Rc<T> {
+    fn from(value: T) -> Self {
+        Rc::new(value)
     }
 }
 
@@ -46,24 +207,35 @@ impl<T> Clone for Rc<T> {
     fn clone(&self) -> Self {
         // SAFETY: `self.shared` is of type `NonNull<Shared<T>>`. The pointer is never null.
         let shared = unsafe { self.shared.as_ref() };
-        let count = shared.ref_count.get();
-        shared.ref_count.set(count + 1);
+        let count = shared.ref_count.get().checked_add(1);
+        shared
+            .ref_count
+            .set(count.expect("reference count overflowed"));
         Rc {
             shared: self.shared,
             _marker: PhantomData,
         }
     }
+
+    fn clone_from(&mut self, source: &Self) {
+        // Both already point to the same allocation, the count stays the same.
+        if self.shared == source.shared {
+            return;
+        }
+        // Dropping the old value decrements its count, cloning increments the new one.
+        *self = source.clone();
+    }
 }
 
 impl<T> Drop for Rc<T> {
     fn drop(&mut self) {
         let shared = unsafe { self.shared.as_ref() };
         let count = shared.ref_count.get();
-        match count {
+        match NonZeroUsize::new(count.get() - 1) {
             // SAFETY: This is the last Rc, and it is dropped. There will be no Rc, and no references to T.
-            1 => drop(unsafe { Box::from_raw(self.shared.as_ptr()) }),
+            None => drop(unsafe { Box::from_raw(self.shared.as_ptr()) }),
             // There are other Rcs, the Box will not be dropped.
-            _ => shared.ref_count.set(count - 1),
+            Some(count) => shared.ref_count.set(count),
         }
     }
 }
@@ -112,4 +284,198 @@ mod tests {
         drop(rc_clone_2);
         assert_eq!(rc_clone_1.ref_count(), 1);
     }
+
+    #[test]
+    fn test_from() {
+        let rc: Rc<i32> = 5.into();
+        assert_eq!(*rc, 5);
+        assert_eq!(rc.ref_count(), 1);
+    }
+
+    #[test]
+    fn test_clone_from_same_allocation() {
+        let mut a = Rc::new(37);
+        let b = Rc::clone(&a);
+        a.clone_from(&b);
+        assert_eq!(a.ref_count(), 2);
+        assert_eq!(*a, 37);
+    }
+
+    #[test]
+    fn test_clone_from_different_allocation() {
+        let mut a = Rc::new(37);
+        let a_clone = Rc::clone(&a);
+        let b = Rc::new(73);
+        a.clone_from(&b);
+        assert_eq!(*a, 73);
+        assert_eq!(a.ref_count(), 2);
+        assert_eq!(b.ref_count(), 2);
+        assert_eq!(a_clone.ref_count(), 1);
+    }
+
+    #[test]
+    fn test_debug_ref_count() {
+        let rc = Rc::new(37);
+        let _rc_clone = Rc::clone(&rc);
+        Rc::debug_ref_count(&rc);
+        assert_eq!(rc.ref_count(), 2);
+    }
+
+    #[cfg(feature = "trace_rc")]
+    #[test]
+    fn test_ref_count_trace_contains_count() {
+        let rc = Rc::new(37);
+        let _rc_clone = Rc::clone(&rc);
+        let trace = Rc::ref_count_trace(&rc);
+        assert!(trace.starts_with("Rc strong count: 2\n"));
+        assert!(trace.contains("test_ref_count_trace_contains_count"));
+    }
+
+    #[test]
+    fn test_try_unwrap_unique() {
+        let rc = Rc::new(String::from("Hello"));
+        assert_eq!(Rc::try_unwrap(rc).ok(), Some(String::from("Hello")));
+    }
+
+    #[test]
+    fn test_try_unwrap_shared() {
+        let rc = Rc::new(37);
+        let rc_clone = Rc::clone(&rc);
+        let rc = Rc::try_unwrap(rc).unwrap_err();
+        assert_eq!(*rc, 37);
+        assert_eq!(rc.ref_count(), 2);
+        drop(rc_clone);
+        assert_eq!(Rc::try_unwrap(rc).ok(), Some(37));
+    }
+
+    struct CloneCounter<'a> {
+        clones: &'a Cell<usize>,
+    }
+
+    impl Clone for CloneCounter<'_> {
+        fn clone(&self) -> Self {
+            self.clones.set(self.clones.get() + 1);
+            CloneCounter {
+                clones: self.clones,
+            }
+        }
+    }
+
+    #[test]
+    fn test_unwrap_or_clone_unique_moves() {
+        let clones = Cell::new(0);
+        let rc = Rc::new(CloneCounter { clones: &clones });
+        let _value = Rc::unwrap_or_clone(rc);
+        assert_eq!(clones.get(), 0);
+    }
+
+    #[test]
+    fn test_unwrap_or_clone_shared_clones() {
+        let clones = Cell::new(0);
+        let rc = Rc::new(CloneCounter { clones: &clones });
+        let rc_clone = Rc::clone(&rc);
+        let _value = Rc::unwrap_or_clone(rc);
+        assert_eq!(clones.get(), 1);
+        assert_eq!(rc_clone.ref_count(), 1);
+    }
+
+    #[test]
+    fn test_covariance() {
+        fn shorten<'a>(rc: Rc<&'static str>) -> Rc<&'a str> {
+            rc
+        }
+
+        let local = String::from("local");
+        let mut rc = shorten(Rc::new("static"));
+        assert_eq!(*rc, "static");
+        rc = Rc::new(local.as_str());
+        assert_eq!(*rc, "local");
+    }
+
+    #[test]
+    fn test_leak() {
+        let rc = Rc::new(String::from("Hello"));
+        let rc_clone = Rc::clone(&rc);
+        let leaked: &'static String = Rc::leak(rc);
+        assert_eq!(leaked, "Hello");
+        assert_eq!(rc_clone.ref_count(), 2);
+
+        let rc_clone_2 = Rc::clone(&rc_clone);
+        assert_eq!(rc_clone.ref_count(), 3);
+        drop(rc_clone_2);
+        drop(rc_clone);
+        // The leaked count keeps the value alive after every other Rc is gone.
+        assert_eq!(leaked, "Hello");
+    }
+
+    #[test]
+    fn test_try_new() {
+        let rc = Rc::try_new(String::from("Hello")).ok().unwrap();
+        let rc_clone = Rc::clone(&rc);
+        assert_eq!(*rc_clone, "Hello");
+        assert_eq!(rc.ref_count(), 2);
+        drop(rc);
+        assert_eq!(Rc::try_unwrap(rc_clone).ok(), Some(String::from("Hello")));
+    }
+
+    #[test]
+    fn test_with() {
+        let rc = Rc::new(String::from("Hello"));
+        assert_eq!(Rc::with(&rc, |s| s.len()), 5);
+        assert_eq!(rc.ref_count(), 1);
+    }
+
+    #[test]
+    fn test_with_borrow() {
+        let rc = Rc::new(RefCell::new(vec![37, 73]));
+        let rc_clone = Rc::clone(&rc);
+        assert_eq!(
+            Rc::with_borrow(&rc_clone, |v| v.iter().sum::<i32>()),
+            Ok(110)
+        );
+
+        let modifier = rc.borrow_mut().unwrap();
+        assert_eq!(Rc::with_borrow(&rc_clone, |v| v.len()), Err(BorrowError));
+        drop(modifier);
+        assert_eq!(Rc::with_borrow(&rc_clone, |v| v.len()), Ok(2));
+    }
+
+    #[test]
+    fn test_clone_n() {
+        struct DropLog<'a>(&'a Cell<usize>);
+        impl Drop for DropLog<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let rc = Rc::new(DropLog(&drops));
+        let clones = Rc::clone_n(&rc, 1000);
+        assert_eq!(clones.len(), 1000);
+        assert_eq!(rc.ref_count(), 1001);
+        assert!(clones.iter().all(|clone| std::ptr::eq(&**clone, &*rc)));
+
+        drop(clones);
+        assert_eq!(rc.ref_count(), 1);
+        assert_eq!(drops.get(), 0);
+        drop(rc);
+        assert_eq!(drops.get(), 1);
+        assert!(Rc::clone_n(&Rc::new(()), 0).is_empty());
+    }
+
+    #[test]
+    fn test_ref_count_through_clone_and_drop_cycles() {
+        let rc = Rc::new(37);
+        for round in 1..=3 {
+            let clones: Vec<_> = (0..round * 10).map(|_| rc.clone()).collect();
+            assert_eq!(rc.ref_count(), round * 10 + 1);
+            drop(clones);
+            assert_eq!(rc.ref_count(), 1);
+        }
+        let clone = rc.clone();
+        drop(rc);
+        assert_eq!(clone.ref_count(), 1);
+        assert_eq!(Rc::try_unwrap(clone).ok(), Some(37));
+    }
 }
@@ -0,0 +1,83 @@
+use crate::cell::Cell;
+
+/// Detects when a computation is re-entered while it is still running, e.g. a `Debug`
+/// impl walking a structure that contains itself.
+pub struct RecursionGuard {
+    active: Cell<bool>,
+}
+
+impl RecursionGuard {
+    pub fn new() -> Self {
+        RecursionGuard {
+            active: Cell::new(false),
+        }
+    }
+
+    /// Marks the guard as active until the returned token is dropped.
+    ///
+    /// Returns `None` if it is already active, meaning the caller was reached again from
+    /// within its own computation.
+    #[must_use = "the guard is released as soon as the token is dropped"]
+    pub fn enter(&self) -> Option<GuardToken<'_>> {
+        if self.active.get() {
+            return None;
+        }
+        self.active.set(true);
+        Some(GuardToken { guard: self })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+}
+
+impl Default for RecursionGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a `RecursionGuard` active, releasing it when dropped.
+pub struct GuardToken<'guard> {
+    guard: &'guard RecursionGuard,
+}
+
+impl Drop for GuardToken<'_> {
+    fn drop(&mut self) {
+        self.guard.active.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter_and_release() {
+        let guard = RecursionGuard::new();
+        let token = guard.enter();
+        assert!(token.is_some());
+        assert!(guard.is_active());
+        assert!(guard.enter().is_none());
+        drop(token);
+        assert!(!guard.is_active());
+        assert!(guard.enter().is_some());
+    }
+
+    #[test]
+    fn test_recursive_call_bails() {
+        fn visit(guard: &RecursionGuard, depth: u32) -> Vec<u32> {
+            let Some(_token) = guard.enter() else {
+                return vec![depth];
+            };
+            // Calls itself once, which has to bail instead of recursing forever.
+            let mut bailed_at = visit(guard, depth + 1);
+            bailed_at.push(depth);
+            bailed_at
+        }
+
+        let guard = RecursionGuard::new();
+        assert_eq!(visit(&guard, 0), [1, 0]);
+        assert!(!guard.is_active());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::cell::Cell;
 use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
 
 /// The state of a `RefCell`, tracking how it is being accessed.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -8,15 +10,109 @@ enum RefCellState {
     Exclusive,
 }
 
+/// The error returned when the value can't be borrowed because it is already mutably borrowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorrowError;
+
+/// The error returned when the value can't be borrowed mutably because it is already borrowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorrowMutError;
+
+impl std::fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("already mutably borrowed")
+    }
+}
+
+impl std::error::Error for BorrowError {}
+
+impl std::fmt::Display for BorrowMutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("already borrowed")
+    }
+}
+
+impl std::error::Error for BorrowMutError {}
+
 /// A cell providing interior mutability with dynamic borrowing.
+///
+/// Conflicting borrows are only detected at runtime, but the guards still borrow the
+/// `RefCell` itself, so the borrow checker keeps them from outliving it:
+///
+/// ```
+/// use rust_exploration::refcell::RefCell;
+///
+/// let data = RefCell::new(37);
+/// {
+///     let mut modifier = data.borrow_mut().unwrap();
+///     *modifier += 1;
+/// }
+/// let observer = data.borrow().unwrap();
+/// assert_eq!(*observer, 38);
+/// ```
+///
+/// A `Ref` can't outlive its `RefCell`:
+///
+/// ```compile_fail
+/// use rust_exploration::refcell::RefCell;
+///
+/// let observer;
+/// {
+///     let data = RefCell::new(37);
+///     observer = data.borrow().unwrap();
+/// }
+/// println!("{}", *observer);
+/// ```
+///
+/// Neither can a `RefMut`:
+///
+/// ```compile_fail
+/// use rust_exploration::refcell::RefCell;
+///
+/// let modifier;
+/// {
+///     let data = RefCell::new(37);
+///     modifier = data.borrow_mut().unwrap();
+/// }
+/// println!("{}", *modifier);
+/// ```
+///
+/// The `RefCell` can't be moved or dropped while a guard exists:
+///
+/// ```compile_fail
+/// use rust_exploration::refcell::RefCell;
+///
+/// let data = RefCell::new(37);
+/// let observer = data.borrow().unwrap();
+/// drop(data);
+/// println!("{}", *observer);
+/// ```
+///
+/// And, like `std::cell::Ref`, a `Ref` only implements `Deref`, so it never hands out a `&mut`:
+///
+/// ```compile_fail
+/// use rust_exploration::refcell::RefCell;
+///
+/// let data = RefCell::new(37);
+/// let mut observer = data.borrow().unwrap();
+/// *observer += 1;
+/// ```
 pub struct RefCell<T> {
     value: UnsafeCell<T>,
     state: Cell<RefCellState>,
 }
 
 /// An immutable reference to the value inside a `RefCell`.
+///
+/// Points at the value, or a part of it after `Ref::map_split`, and at the state of the
+/// `RefCell` it borrows from.
 pub struct Ref<'refcell, T> {
-    refcell: &'refcell RefCell<T>,
+    value: NonNull<T>,
+    state: &'refcell Cell<RefCellState>,
+    // Only a `Ref` to the whole value may be upgraded by `RefCell::borrow_mut_from_ref`.
+    // A part returned by `map_split` is reached through a shared reference, which must
+    // never be written through.
+    whole: bool,
 }
 
 impl<T> std::ops::Deref for Ref<'_, T> {
@@ -25,29 +121,74 @@ impl<T> std::ops::Deref for Ref<'_, T> {
         // SAFETY: A `Ref` is only created if no exclusive references exist.
         // State is set to Shared and no exclusive reference will be given out.
         // Dereferencing into a shared reference is safe.
-        unsafe { &*self.refcell.value.get() }
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<'refcell, T> Ref<'refcell, T> {
+    /// Splits the borrow into two borrows of parts of the value, e.g. two fields.
+    ///
+    /// Both count as shared borrows, the `RefCell` can be borrowed mutably again once
+    /// both are dropped. An associated function, so it can't shadow a method on `T`.
+    pub fn map_split<U, V>(
+        orig: Ref<'refcell, T>,
+        f: impl FnOnce(&T) -> (&U, &V),
+    ) -> (Ref<'refcell, U>, Ref<'refcell, V>) {
+        let state = orig.state;
+        let (u, v) = f(&orig);
+        let (u, v) = (NonNull::from(u), NonNull::from(v));
+        match state.get() {
+            // `orig` is forgotten below, so its borrow is handed over to the first part.
+            RefCellState::Shared(count) => state.set(RefCellState::Shared(count + 1)),
+            RefCellState::Exclusive => unreachable!(),
+        }
+        std::mem::forget(orig);
+        (
+            Ref {
+                value: u,
+                state,
+                whole: false,
+            },
+            Ref {
+                value: v,
+                state,
+                whole: false,
+            },
+        )
+    }
+}
+
+// The guards forward the value's `AsRef`/`AsMut` impls, so a `Ref<String>` can be passed
+// wherever an `impl AsRef<str>` is expected.
+
+impl<T: AsRef<U>, U: ?Sized> AsRef<U> for Ref<'_, T> {
+    fn as_ref(&self) -> &U {
+        (**self).as_ref()
     }
 }
 
 impl<T> Drop for Ref<'_, T> {
     fn drop(&mut self) {
-        match self.refcell.state.get() {
+        match self.state.get() {
             RefCellState::Exclusive | RefCellState::Shared(0) => unreachable!(),
-            RefCellState::Shared(count) => self.refcell.state.set(RefCellState::Shared(count - 1)),
+            RefCellState::Shared(count) => self.state.set(RefCellState::Shared(count - 1)),
         }
     }
 }
 
 /// A mutable reference to the value inside a `RefCell`.
 pub struct RefMut<'refcell, T> {
-    refcell: &'refcell RefCell<T>,
+    value: NonNull<T>,
+    state: &'refcell Cell<RefCellState>,
+    // Invariant in `T`, like a `&mut T` would be.
+    _marker: PhantomData<&'refcell mut T>,
 }
 
 impl<T> std::ops::Deref for RefMut<'_, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
         // SAFETY: see safety for DerefMut
-        unsafe { &*self.refcell.value.get() }
+        unsafe { self.value.as_ref() }
     }
 }
 
@@ -56,15 +197,46 @@ impl<T> std::ops::DerefMut for RefMut<'_, T> {
         // SAFETY: A `RefMut` is only created if no other references exist.
         // State is set to Exclusive and no future references are given out.
         // An exclusive lease has been acquired on the inner value and mutably dereferencing is allowed.
-        unsafe { &mut *self.refcell.value.get() }
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<'refcell, T> RefMut<'refcell, T> {
+    /// Turns the exclusive borrow into a shared one, without the value ever being unborrowed
+    /// in between.
+    ///
+    /// An associated function, like `RefCell::borrow_mut_from_ref`, so it can't shadow a
+    /// `downgrade` method on `T`.
+    pub fn downgrade(this: RefMut<'refcell, T>) -> Ref<'refcell, T> {
+        let (value, state) = (this.value, this.state);
+        // `this` is forgotten so its Drop doesn't reset the state to Shared(0).
+        std::mem::forget(this);
+        state.set(RefCellState::Shared(1));
+        Ref {
+            value,
+            state,
+            whole: true,
+        }
+    }
+}
+
+impl<T: AsRef<U>, U: ?Sized> AsRef<U> for RefMut<'_, T> {
+    fn as_ref(&self) -> &U {
+        (**self).as_ref()
+    }
+}
+
+impl<T: AsMut<U>, U: ?Sized> AsMut<U> for RefMut<'_, T> {
+    fn as_mut(&mut self) -> &mut U {
+        (**self).as_mut()
     }
 }
 
 impl<T> Drop for RefMut<'_, T> {
     fn drop(&mut self) {
-        match self.refcell.state.get() {
+        match self.state.get() {
             RefCellState::Shared(_) => unreachable!(),
-            RefCellState::Exclusive => self.refcell.state.set(RefCellState::Shared(0)),
+            RefCellState::Exclusive => self.state.set(RefCellState::Shared(0)),
         }
     }
 }
@@ -83,7 +255,12 @@ impl<T> RefCell<T> {
         match self.state.get() {
             RefCellState::Shared(count) => {
                 self.state.set(RefCellState::Shared(count + 1));
-                Some(Ref { refcell: self })
+                Some(Ref {
+                    // SAFETY: `UnsafeCell::get` never returns a null pointer.
+                    value: unsafe { NonNull::new_unchecked(self.value.get()) },
+                    state: &self.state,
+                    whole: true,
+                })
             }
             RefCellState::Exclusive => None,
         }
@@ -95,11 +272,153 @@ impl<T> RefCell<T> {
             RefCellState::Shared(0) => {
                 // SAFETY: no other references are currently given, because state is Shared(0).
                 self.state.set(RefCellState::Exclusive);
-                Some(RefMut { refcell: self })
+                Some(RefMut {
+                    // SAFETY: `UnsafeCell::get` never returns a null pointer.
+                    value: unsafe { NonNull::new_unchecked(self.value.get()) },
+                    state: &self.state,
+                    _marker: PhantomData,
+                })
             }
             RefCellState::Shared(_) | RefCellState::Exclusive => None,
         }
     }
+
+    /// Upgrades a shared borrow into an exclusive one if it is the only outstanding borrow,
+    /// otherwise returns the original `Ref`.
+    ///
+    /// A part returned by `Ref::map_split` is never upgraded.
+    pub fn borrow_mut_from_ref(r: Ref<'_, T>) -> Result<RefMut<'_, T>, Ref<'_, T>> {
+        let (value, state) = (r.value, r.state);
+        match state.get() {
+            RefCellState::Shared(1) if r.whole => {
+                // SAFETY: `r` is the only reference given out, it is forgotten below
+                // so its Drop doesn't decrement the state that is now Exclusive.
+                state.set(RefCellState::Exclusive);
+                std::mem::forget(r);
+                Ok(RefMut {
+                    value,
+                    state,
+                    _marker: PhantomData,
+                })
+            }
+            RefCellState::Shared(_) => Err(r),
+            RefCellState::Exclusive => unreachable!(),
+        }
+    }
+
+    /// Borrows the value mutably and never releases the borrow, returning a `&mut T` that
+    /// lives as long as the `RefCell`.
+    ///
+    /// Every later `borrow` and `borrow_mut` fails, because the state stays `Exclusive`.
+    /// Panics if the value is currently borrowed.
+    // The exclusive borrow that is never released is what makes the `&mut` unique.
+    #[allow(clippy::mut_from_ref)]
+    pub fn leak(&self) -> &mut T {
+        let modifier = self.borrow_mut().expect("RefCell is already borrowed");
+        let mut value = modifier.value;
+        // Forgetting the guard keeps the state Exclusive for the rest of the cell's life.
+        std::mem::forget(modifier);
+        // SAFETY: the exclusive borrow is never released, so no other reference to the
+        // value can be created while `&self` is alive.
+        unsafe { value.as_mut() }
+    }
+
+    /// Mutably borrows the value for the duration of `f`.
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn modify(&self, f: impl FnOnce(&mut T)) {
+        let mut value = self.borrow_mut().expect("RefCell is already borrowed");
+        f(&mut value);
+    }
+
+    /// Mutably borrows the value for the duration of `f`.
+    ///
+    /// Returns `BorrowMutError` instead of panicking if the value is currently borrowed.
+    pub fn update(&self, f: impl FnOnce(&mut T)) -> Result<(), BorrowMutError> {
+        let mut value = self.borrow_mut().ok_or(BorrowMutError)?;
+        f(&mut value);
+        Ok(())
+    }
+
+    /// Runs `f` on a clone of the value and stores the clone back only if `f` returns `Ok`,
+    /// so an `Err` leaves the value exactly as it was.
+    ///
+    /// The value stays mutably borrowed while `f` runs. Panics if it is currently borrowed.
+    pub fn transaction<R, E>(&self, f: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone,
+    {
+        let mut value = self.borrow_mut().expect("RefCell is already borrowed");
+        let mut draft = value.clone();
+        let result = f(&mut draft)?;
+        *value = draft;
+        Ok(result)
+    }
+
+    /// Immutably borrows the value for the duration of `f` and returns its result.
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn with_borrow<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let value = self.borrow().expect("already mutably borrowed");
+        f(&value)
+    }
+
+    /// Mutably borrows the value for the duration of `f` and returns its result.
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with_borrow_mut<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut value = self.borrow_mut().expect("RefCell is already borrowed");
+        f(&mut value)
+    }
+
+    /// Returns a clone of the value, or `None` instead of panicking if it is currently
+    /// mutably borrowed.
+    pub fn try_clone_inner(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.borrow().map(|value| value.clone())
+    }
+}
+
+// The comparisons take shared borrows of both values and panic if either is mutably borrowed.
+
+impl<T: PartialEq> PartialEq for RefCell<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.borrow().expect("already mutably borrowed")
+            == *other.borrow().expect("already mutably borrowed")
+    }
+}
+
+impl<T: Eq> Eq for RefCell<T> {}
+
+impl<T: PartialOrd> PartialOrd for RefCell<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.borrow()
+            .expect("already mutably borrowed")
+            .partial_cmp(&*other.borrow().expect("already mutably borrowed"))
+    }
+}
+
+impl<T: Ord> Ord for RefCell<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.borrow()
+            .expect("already mutably borrowed")
+            .cmp(&*other.borrow().expect("already mutably borrowed"))
+    }
+}
+
+impl<T: std::hash::Hash> std::hash::Hash for RefCell<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.borrow().expect("already mutably borrowed").hash(state)
+    }
+}
+
+impl<T> From<T> for RefCell<T> {
+    /// Creates a new `RefCell` containing `value`.
+    fn from(value: T) -> Self {
+        RefCell::new(value)
+    }
 }
 
 #[cfg(test)]
@@ -154,4 +473,317 @@ mod tests {
         assert_eq!(*observer.unwrap(), 37);
         let _ = *modifier.unwrap();
     }
+
+    #[test]
+    fn test_from() {
+        let data: RefCell<i32> = 5.into();
+        assert_eq!(*data.borrow().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_modify() {
+        let data = RefCell::new(vec![37]);
+        data.modify(|v| v.push(73));
+        data.modify(|v| v.push(137));
+        assert_eq!(*data.borrow().unwrap(), vec![37, 73, 137]);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_modify_while_borrowed() {
+        let data = RefCell::new(vec![37]);
+        let _observer = data.borrow();
+        data.modify(|v| v.clear());
+    }
+
+    #[test]
+    fn test_borrow_mut_from_ref_upgrades_only_borrow() {
+        let data = RefCell::new(37);
+        let observer = data.borrow().unwrap();
+        let mut modifier = RefCell::borrow_mut_from_ref(observer).ok().unwrap();
+        assert_eq!(data.state.get(), RefCellState::Exclusive);
+        *modifier = 73;
+        drop(modifier);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+        assert_eq!(*data.borrow().unwrap(), 73);
+    }
+
+    #[test]
+    fn test_borrow_mut_from_ref_fails_with_other_borrows() {
+        let data = RefCell::new(37);
+        let observer_1 = data.borrow().unwrap();
+        let observer_2 = data.borrow().unwrap();
+        let observer_1 = RefCell::borrow_mut_from_ref(observer_1).err().unwrap();
+        assert_eq!(*observer_1, 37);
+        assert_eq!(data.state.get(), RefCellState::Shared(2));
+        drop(observer_2);
+        assert!(RefCell::borrow_mut_from_ref(observer_1).is_ok());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let data = RefCell::new(37);
+        assert!(data == RefCell::new(37));
+        // Comparing with itself takes two shared borrows at once.
+        let same = &data;
+        assert!(data == *same);
+        assert!(RefCell::new(37) < RefCell::new(73));
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_comparison_while_mutably_borrowed() {
+        let data = RefCell::new(37);
+        let _modifier = data.borrow_mut();
+        let _ = data == RefCell::new(37);
+    }
+
+    // Keys must not be mutated while they are in a collection.
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn test_in_btree_set() {
+        let set: std::collections::BTreeSet<_> =
+            [73, 37, 137, 37].into_iter().map(RefCell::new).collect();
+        let values: Vec<_> = set.iter().map(|value| *value.borrow().unwrap()).collect();
+        assert_eq!(values, [37, 73, 137]);
+        assert!(set.contains(&RefCell::new(137)));
+    }
+
+    #[test]
+    fn test_update() {
+        let data = RefCell::new(vec![37]);
+        assert_eq!(data.update(|v| v.push(73)), Ok(()));
+        assert_eq!(*data.borrow().unwrap(), vec![37, 73]);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    fn test_update_while_borrowed() {
+        let data = RefCell::new(vec![37]);
+        let observer = data.borrow().unwrap();
+        assert_eq!(data.update(|v| v.clear()), Err(BorrowMutError));
+        assert_eq!(*observer, vec![37]);
+        drop(observer);
+
+        let _modifier = data.borrow_mut().unwrap();
+        assert_eq!(data.update(|v| v.clear()), Err(BorrowMutError));
+    }
+
+    #[test]
+    fn test_with_borrow() {
+        let data = RefCell::new(vec![37, 73]);
+        let len = data.with_borrow(|v| {
+            assert_eq!(data.state.get(), RefCellState::Shared(1));
+            v.len()
+        });
+        assert_eq!(len, 2);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    fn test_with_borrow_mut() {
+        let data = RefCell::new(vec![37]);
+        let popped = data.with_borrow_mut(|v| {
+            assert_eq!(data.state.get(), RefCellState::Exclusive);
+            v.push(73);
+            v.pop()
+        });
+        assert_eq!(popped, Some(73));
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+        assert_eq!(data.with_borrow(|v| v.clone()), vec![37]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already mutably borrowed")]
+    fn test_with_borrow_inside_with_borrow_mut_panics() {
+        let data = RefCell::new(37);
+        data.with_borrow_mut(|_| data.with_borrow(|v| *v));
+    }
+
+    #[test]
+    fn test_ref_as_ref() {
+        fn len(s: impl AsRef<str>) -> usize {
+            s.as_ref().len()
+        }
+        fn sum(v: impl AsRef<[i32]>) -> i32 {
+            v.as_ref().iter().sum()
+        }
+
+        let text = RefCell::new(String::from("foo"));
+        assert_eq!(len(text.borrow().unwrap()), 3);
+        assert_eq!(len(text.borrow_mut().unwrap()), 3);
+        let numbers = RefCell::new(vec![37, 73]);
+        assert_eq!(sum(numbers.borrow().unwrap()), 110);
+    }
+
+    #[test]
+    fn test_ref_mut_as_mut() {
+        fn double(mut v: impl AsMut<[i32]>) {
+            v.as_mut().iter_mut().for_each(|n| *n *= 2);
+        }
+
+        let numbers = RefCell::new(vec![37, 73]);
+        let mut modifier = numbers.borrow_mut().unwrap();
+        double(&mut modifier);
+        double(modifier);
+        assert_eq!(*numbers.borrow().unwrap(), [148, 292]);
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let data = RefCell::new(vec![37]);
+        let mut modifier = data.borrow_mut().unwrap();
+        modifier.push(73);
+        let observer = RefMut::downgrade(modifier);
+        assert_eq!(data.state.get(), RefCellState::Shared(1));
+        assert_eq!(*observer, [37, 73]);
+
+        // Other shared borrows are allowed now, exclusive ones still aren't.
+        assert_eq!(*data.borrow().unwrap(), [37, 73]);
+        assert!(data.borrow_mut().is_none());
+        drop(observer);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    fn test_map_split() {
+        let data = RefCell::new((37, String::from("foo")));
+        let (number, text) = Ref::map_split(data.borrow().unwrap(), |(n, s)| (n, s));
+        assert_eq!(data.state.get(), RefCellState::Shared(2));
+        assert_eq!(*number, 37);
+        assert_eq!(*text, "foo");
+        assert!(data.borrow_mut().is_none());
+
+        drop(number);
+        assert_eq!(data.state.get(), RefCellState::Shared(1));
+        assert_eq!(text.len(), 3);
+        drop(text);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+        assert!(data.borrow_mut().is_some());
+    }
+
+    #[test]
+    fn test_map_split_part_is_not_upgraded() {
+        let data = RefCell::new((String::from("foo"), String::from("bar")));
+        let (first, second) = Ref::map_split(data.borrow().unwrap(), |(a, b)| (a, b));
+        drop(second);
+        assert_eq!(data.state.get(), RefCellState::Shared(1));
+        let first = RefCell::borrow_mut_from_ref(first).err().unwrap();
+        assert_eq!(*first, "foo");
+    }
+
+    fn panic_while(f: impl FnOnce()) {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_guards_restore_state_on_panic() {
+        let data = RefCell::new((37, String::from("foo")));
+
+        panic_while(|| {
+            let mut modifier = data.borrow_mut().unwrap();
+            modifier.0 += 1;
+            panic!("while holding a RefMut");
+        });
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+
+        panic_while(|| data.with_borrow_mut(|_| panic!("inside with_borrow_mut")));
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+
+        panic_while(|| {
+            let _observer = RefMut::downgrade(data.borrow_mut().unwrap());
+            panic!("while holding a downgraded Ref");
+        });
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+        assert_eq!(data.borrow_mut().unwrap().0, 38);
+    }
+
+    #[test]
+    fn test_map_split_restores_state_on_panic() {
+        let data = RefCell::new((37, String::from("foo")));
+
+        panic_while(|| {
+            let (_number, _text) = Ref::map_split(data.borrow().unwrap(), |(n, s)| (n, s));
+            panic!("while holding both parts");
+        });
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+
+        panic_while(|| {
+            let _parts: (Ref<i32>, Ref<String>) =
+                Ref::map_split(data.borrow().unwrap(), |_| panic!("inside map_split"));
+        });
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+        assert!(data.borrow_mut().is_some());
+    }
+
+    #[test]
+    fn test_leak() {
+        let data = RefCell::new(vec![37]);
+        let leaked = data.leak();
+        leaked.push(73);
+        assert_eq!(*leaked, [37, 73]);
+        assert_eq!(data.state.get(), RefCellState::Exclusive);
+        assert!(data.borrow().is_none());
+        assert!(data.borrow_mut().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "RefCell is already borrowed")]
+    fn test_leak_while_borrowed() {
+        let data = RefCell::new(37);
+        let _observer = data.borrow().unwrap();
+        data.leak();
+    }
+
+    #[test]
+    fn test_transaction_commits_on_ok() {
+        let data = RefCell::new(vec![37]);
+        let result: Result<usize, ()> = data.transaction(|v| {
+            v.push(73);
+            Ok(v.len())
+        });
+        assert_eq!(result, Ok(2));
+        assert_eq!(*data.borrow().unwrap(), [37, 73]);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_err() {
+        let data = RefCell::new(vec![37]);
+        let result: Result<(), &str> = data.transaction(|v| {
+            v.clear();
+            v.push(0);
+            Err("invalid")
+        });
+        assert_eq!(result, Err("invalid"));
+        assert_eq!(*data.borrow().unwrap(), [37]);
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
+
+    #[test]
+    fn test_error_display() {
+        use std::error::Error;
+        assert_eq!(BorrowError.to_string(), "already mutably borrowed");
+        assert_eq!(BorrowMutError.to_string(), "already borrowed");
+        assert!(BorrowError.source().is_none());
+        assert!(BorrowMutError.source().is_none());
+    }
+
+    #[test]
+    fn test_try_clone_inner() {
+        let data = RefCell::new(String::from("Hello"));
+        assert_eq!(data.try_clone_inner().as_deref(), Some("Hello"));
+        {
+            let _observer = data.borrow().unwrap();
+            assert_eq!(data.try_clone_inner().as_deref(), Some("Hello"));
+        }
+        {
+            let _modifier = data.borrow_mut().unwrap();
+            assert_eq!(data.try_clone_inner(), None);
+        }
+        assert_eq!(data.state.get(), RefCellState::Shared(0));
+    }
 }
@@ -0,0 +1,211 @@
+use std::marker::PhantomData;
+use std::mem::{size_of, MaybeUninit};
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+const WORD: usize = size_of::<usize>();
+
+/// Values a `SeqLock` can hold: `Copy` types whose bytes are all initialized.
+///
+/// # Safety
+///
+/// The type must have no padding, since the lock copies its bytes into atomic words and
+/// reading a padding byte is undefined behavior.
+pub unsafe trait NoPadding: Copy {}
+
+macro_rules! impl_no_padding {
+    ($($t:ty),*) => {
+        $(
+            // SAFETY: primitives have no padding.
+            unsafe impl NoPadding for $t {}
+        )*
+    };
+}
+
+impl_no_padding!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool);
+
+// SAFETY: array elements are laid out back to back, and the elements have no padding.
+unsafe impl<T: NoPadding, const N: usize> NoPadding for [T; N] {}
+
+// SAFETY: both fields have the same size and alignment, so no padding fits between or
+// after them.
+unsafe impl<T: NoPadding> NoPadding for (T, T) {}
+
+/// A lock for read-mostly `Copy` data: readers never block writers and never write
+/// shared memory, they just retry if a write happened while they were reading.
+///
+/// The sequence number is odd while a write is in progress. A reader that saw the same
+/// even number before and after copying the value knows the copy isn't torn.
+///
+/// The value is stored as atomic words, so a read overlapping a write is a race on
+/// atomics, not a data race. Torn copies are thrown away before they become a `T`.
+pub struct SeqLock<T> {
+    seq: AtomicUsize,
+    words: Box<[AtomicUsize]>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: NoPadding> SeqLock<T> {
+    pub fn new(value: T) -> Self {
+        let len = size_of::<T>().div_ceil(WORD);
+        let lock = SeqLock {
+            seq: AtomicUsize::new(0),
+            words: (0..len).map(|_| AtomicUsize::new(0)).collect(),
+            _marker: PhantomData,
+        };
+        lock.store_words(&value);
+        lock
+    }
+
+    /// Returns a copy of the value, retrying while a write overlaps the read.
+    pub fn read(&self) -> T {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before & 1 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let value = self.load_words();
+            // Keeps the words read from moving after the second load of the sequence number.
+            fence(Ordering::Acquire);
+            let after = self.seq.load(Ordering::Relaxed);
+            if before == after {
+                // SAFETY: the sequence number didn't change, so no write overlapped the
+                // copy and it holds every byte of a `T` that was written.
+                return unsafe { value.assume_init() };
+            }
+        }
+    }
+
+    /// Replaces the value. Concurrent writers wait for each other.
+    pub fn write(&self, value: T) {
+        let mut seq = self.seq.load(Ordering::Relaxed);
+        loop {
+            if seq & 1 == 0 {
+                match self.seq.compare_exchange_weak(
+                    seq,
+                    seq + 1,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(actual) => seq = actual,
+                }
+            } else {
+                std::hint::spin_loop();
+                seq = self.seq.load(Ordering::Relaxed);
+            }
+        }
+        // Keeps the words written from moving before the odd sequence number is published.
+        fence(Ordering::Release);
+        self.store_words(&value);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    /// Copies the bytes of `value` into the words, the last one padded with zeros.
+    fn store_words(&self, value: &T) {
+        let bytes = value as *const T as *const u8;
+        for (i, word) in self.words.iter().enumerate() {
+            let offset = i * WORD;
+            let mut chunk = [0u8; WORD];
+            let len = WORD.min(size_of::<T>() - offset);
+            // SAFETY: `offset + len` stays within `value`, whose bytes are all initialized
+            // because `T: NoPadding`.
+            unsafe { std::ptr::copy_nonoverlapping(bytes.add(offset), chunk.as_mut_ptr(), len) };
+            word.store(usize::from_ne_bytes(chunk), Ordering::Relaxed);
+        }
+    }
+
+    /// Copies the words into a possibly torn `T`, which is only valid once validated.
+    fn load_words(&self) -> MaybeUninit<T> {
+        let mut value = MaybeUninit::<T>::uninit();
+        let bytes = value.as_mut_ptr() as *mut u8;
+        for (i, word) in self.words.iter().enumerate() {
+            let offset = i * WORD;
+            let chunk = word.load(Ordering::Relaxed).to_ne_bytes();
+            let len = WORD.min(size_of::<T>() - offset);
+            // SAFETY: `offset + len` stays within `value`.
+            unsafe { std::ptr::copy_nonoverlapping(chunk.as_ptr(), bytes.add(offset), len) };
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread::spawn;
+
+    #[test]
+    fn test_read_and_write() {
+        let lock = SeqLock::new((37, 73));
+        assert_eq!(lock.read(), (37, 73));
+        lock.write((1, 2));
+        assert_eq!(lock.read(), (1, 2));
+    }
+
+    #[test]
+    fn test_readers_never_observe_torn_values() {
+        let lock: &'static _ = Box::leak(Box::new(SeqLock::new([0_u64; 8])));
+        let done: &'static _ = Box::leak(Box::new(AtomicBool::new(false)));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                spawn(move || {
+                    let mut last = 0;
+                    while !done.load(Ordering::Acquire) {
+                        let value = lock.read();
+                        assert!(value.iter().all(|&x| x == value[0]), "torn: {value:?}");
+                        assert!(value[0] >= last, "went back from {last} to {}", value[0]);
+                        last = value[0];
+                    }
+                })
+            })
+            .collect();
+        let writer = spawn(move || {
+            for i in 1..=100_000 {
+                lock.write([i; 8]);
+            }
+            done.store(true, Ordering::Release);
+        });
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(lock.read(), [100_000; 8]);
+    }
+
+    #[test]
+    fn test_concurrent_writers() {
+        let lock: &'static _ = Box::leak(Box::new(SeqLock::new((0_u64, 0_u64))));
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                spawn(move || {
+                    for j in 0..1000 {
+                        lock.write((i * 1000 + j, i * 1000 + j));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let (a, b) = lock.read();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_sizes_not_a_multiple_of_a_word() {
+        let lock = SeqLock::new([1_u8, 2, 3]);
+        assert_eq!(lock.read(), [1, 2, 3]);
+        lock.write([4, 5, 6]);
+        assert_eq!(lock.read(), [4, 5, 6]);
+
+        let lock = SeqLock::new((true, false));
+        lock.write((false, true));
+        assert_eq!(lock.read(), (false, true));
+
+        let lock = SeqLock::new([0_u16; 0]);
+        assert_eq!(lock.read(), []);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::channel::{SendError, TryRecvError};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// A FIFO queue keeping its first `INLINE` messages in a fixed array, and only moving
+/// to a heap-allocated `VecDeque` once more are waiting at the same time.
+struct SmallQueue<T, const INLINE: usize> {
+    // A ring buffer of `len` messages starting at `head`.
+    inline: [Option<T>; INLINE],
+    head: usize,
+    len: usize,
+    // Every message here arrived after all messages in `inline`, so new messages keep
+    // going here until `inline` is drained and `spill` is empty again.
+    spill: VecDeque<T>,
+}
+
+impl<T, const INLINE: usize> SmallQueue<T, INLINE> {
+    fn new() -> Self {
+        SmallQueue {
+            inline: std::array::from_fn(|_| None),
+            head: 0,
+            len: 0,
+            spill: VecDeque::new(),
+        }
+    }
+
+    fn push_back(&mut self, t: T) {
+        if self.spill.is_empty() && self.len < INLINE {
+            self.inline[(self.head + self.len) % INLINE] = Some(t);
+            self.len += 1;
+        } else {
+            self.spill.push_back(t);
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return self.spill.pop_front();
+        }
+        let t = self.inline[self.head].take();
+        self.head = (self.head + 1) % INLINE;
+        self.len -= 1;
+        t
+    }
+}
+
+struct Inner<T, const INLINE: usize> {
+    queue: SmallQueue<T, INLINE>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+struct Shared<T, const INLINE: usize> {
+    inner: Mutex<Inner<T, INLINE>>,
+    available: Condvar,
+}
+
+/// The sending half of a channel created by `small_channel`.
+pub struct Sender<T, const INLINE: usize> {
+    shared: Arc<Shared<T, INLINE>>,
+}
+
+impl<T, const INLINE: usize> Sender<T, INLINE> {
+    pub fn send(&mut self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if !inner.receiver_alive {
+            return Err(SendError(t));
+        }
+        inner.queue.push_back(t);
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+}
+
+impl<T, const INLINE: usize> Clone for Sender<T, INLINE> {
+    fn clone(&self) -> Self {
+        self.shared.inner.lock().unwrap().senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T, const INLINE: usize> Drop for Sender<T, INLINE> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        inner.senders -= 1;
+        let was_last = inner.senders == 0;
+        drop(inner);
+
+        if was_last {
+            self.shared.available.notify_one();
+        }
+    }
+}
+
+/// The receiving half of a channel created by `small_channel`.
+pub struct Receiver<T, const INLINE: usize> {
+    shared: Arc<Shared<T, INLINE>>,
+}
+
+impl<T, const INLINE: usize> Receiver<T, INLINE> {
+    /// Receives a message, blocking while the channel is empty.
+    ///
+    /// Returns `None` once the channel is empty and all senders are gone.
+    pub fn receive(&mut self) -> Option<T> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        loop {
+            if let Some(t) = inner.queue.pop_front() {
+                return Some(t);
+            }
+            if inner.senders == 0 {
+                return None;
+            }
+            inner = self.shared.available.wait(inner).unwrap();
+        }
+    }
+
+    /// Receives a message if one is available, without blocking.
+    pub fn try_receive(&mut self) -> Result<T, TryRecvError> {
+        let mut inner = self.shared.inner.lock().unwrap();
+        match inner.queue.pop_front() {
+            Some(t) => Ok(t),
+            None if inner.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+impl<T, const INLINE: usize> Drop for Receiver<T, INLINE> {
+    fn drop(&mut self) {
+        self.shared.inner.lock().unwrap().receiver_alive = false;
+    }
+}
+
+impl<T, const INLINE: usize> Iterator for Receiver<T, INLINE> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receive()
+    }
+}
+
+/// Creates an unbounded channel that keeps up to `INLINE` waiting messages inline,
+/// without allocating for them.
+///
+/// Only allocates once, for the state shared by both halves, as long as no more than
+/// `INLINE` messages wait at the same time. Past that, messages spill into a
+/// `VecDeque`, and the channel works like `channel()`.
+pub fn small_channel<T, const INLINE: usize>() -> (Sender<T, INLINE>, Receiver<T, INLINE>) {
+    let inner = Inner {
+        queue: SmallQueue::new(),
+        senders: 1,
+        receiver_alive: true,
+    };
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(inner),
+        available: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_zero_inline_always_spills() {
+        let (mut tx, mut rx) = small_channel::<&str, 0>();
+        tx.send("a").unwrap();
+        tx.send("b").unwrap();
+        assert_eq!(rx.try_receive(), Ok("a"));
+        assert_eq!(rx.try_receive(), Ok("b"));
+        assert_eq!(rx.try_receive(), Err(TryRecvError::Empty));
+        drop(tx);
+        assert_eq!(rx.try_receive(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_across_threads() {
+        let (tx, rx) = small_channel::<u32, 8>();
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let mut tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        tx.send(p * 100 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let mut received: Vec<_> = rx.collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        received.sort_unstable();
+        assert_eq!(received, (0..400).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_send_fails_without_receiver() {
+        let (mut tx, rx) = small_channel::<i32, 2>();
+        drop(rx);
+        assert_eq!(tx.send(37), Err(SendError(37)));
+    }
+}
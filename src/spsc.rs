@@ -0,0 +1,199 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A ring buffer whose positions run from 0 to 2 * capacity, so a full buffer
+/// (tail is capacity ahead of head) can be told apart from an empty one (head == tail).
+struct Shared<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Next position to read, only written by the Receiver.
+    head: AtomicUsize,
+    // Next position to write, only written by the Sender.
+    tail: AtomicUsize,
+}
+
+// SAFETY: a slot is only accessed by the Sender before it is published through `tail`
+// and only by the Receiver after that, until it is released again through `head`.
+unsafe impl<T> Sync for Shared<T> where T: Send {}
+
+impl<T> Shared<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn advance(&self, position: usize) -> usize {
+        (position + 1) % (2 * self.capacity())
+    }
+
+    fn len(&self, head: usize, tail: usize) -> usize {
+        (tail + 2 * self.capacity() - head) % (2 * self.capacity())
+    }
+
+    fn slot(&self, position: usize) -> *mut MaybeUninit<T> {
+        self.slots[position % self.capacity()].get()
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        while head != tail {
+            // SAFETY: slots between head and tail hold values that were sent but never received.
+            unsafe { (*self.slot(head)).assume_init_drop() };
+            head = self.advance(head);
+        }
+    }
+}
+
+/// The sending half of a single-producer single-consumer channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `t`, or gives it back if the buffer is full.
+    pub fn try_send(&mut self, t: T) -> Result<(), T> {
+        // Only this Sender writes `tail`, so its own last store can be read with Relaxed.
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        // Acquire pairs with the Receiver's Release store, so the slot is no longer being read.
+        let head = self.shared.head.load(Ordering::Acquire);
+        if self.shared.len(head, tail) == self.shared.capacity() {
+            return Err(t);
+        }
+        // SAFETY: the slot at `tail` is not between head and tail, so the Receiver isn't
+        // accessing it, and there is only one Sender.
+        unsafe { (*self.shared.slot(tail)).write(t) };
+        // Release publishes the written slot to the Receiver.
+        self.shared
+            .tail
+            .store(self.shared.advance(tail), Ordering::Release);
+        Ok(())
+    }
+}
+
+/// The receiving half of a single-producer single-consumer channel.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receives the oldest message, or returns `None` if the buffer is empty.
+    pub fn try_receive(&mut self) -> Option<T> {
+        // Only this Receiver writes `head`, so its own last store can be read with Relaxed.
+        let head = self.shared.head.load(Ordering::Relaxed);
+        // Acquire pairs with the Sender's Release store, so the slot is fully written.
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // SAFETY: the slot at `head` was initialized and published by the Sender,
+        // and the Sender won't touch it until `head` moves past it.
+        let t = unsafe { (*self.shared.slot(head)).assume_init_read() };
+        // Release hands the now empty slot back to the Sender.
+        self.shared
+            .head
+            .store(self.shared.advance(head), Ordering::Release);
+        Some(t)
+    }
+}
+
+/// Creates a lock-free single-producer single-consumer channel holding up to `capacity`
+/// messages.
+///
+/// Panics if `capacity` is 0.
+pub fn spsc_channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity != 0, "capacity must be non-zero");
+    let shared = Arc::new(Shared {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_empty() {
+        let (_tx, mut rx) = spsc_channel::<i32>(2);
+        assert_eq!(rx.try_receive(), None);
+    }
+
+    #[test]
+    fn test_full() {
+        let (mut tx, mut rx) = spsc_channel(2);
+        assert_eq!(tx.try_send(1), Ok(()));
+        assert_eq!(tx.try_send(2), Ok(()));
+        assert_eq!(tx.try_send(3), Err(3));
+        assert_eq!(rx.try_receive(), Some(1));
+        assert_eq!(tx.try_send(3), Ok(()));
+        assert_eq!(rx.try_receive(), Some(2));
+        assert_eq!(rx.try_receive(), Some(3));
+        assert_eq!(rx.try_receive(), None);
+    }
+
+    #[test]
+    fn test_wraps_around_many_times() {
+        let (mut tx, mut rx) = spsc_channel(3);
+        for i in 0..100 {
+            tx.try_send(i).unwrap();
+            tx.try_send(i + 1000).unwrap();
+            assert_eq!(rx.try_receive(), Some(i));
+            assert_eq!(rx.try_receive(), Some(i + 1000));
+        }
+        assert_eq!(rx.try_receive(), None);
+    }
+
+    #[test]
+    fn test_drops_unreceived_messages() {
+        let value = std::rc::Rc::new(());
+        let (mut tx, mut rx) = spsc_channel(4);
+        for _ in 0..3 {
+            tx.try_send(std::rc::Rc::clone(&value)).unwrap();
+        }
+        drop(rx.try_receive());
+        assert_eq!(std::rc::Rc::strong_count(&value), 3);
+        drop((tx, rx));
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_producer_and_consumer_threads() {
+        const ITEMS: usize = 100_000;
+        let (mut tx, mut rx) = spsc_channel(64);
+        let producer = thread::spawn(move || {
+            for i in 0..ITEMS {
+                let mut item = i;
+                while let Err(returned) = tx.try_send(item) {
+                    item = returned;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < ITEMS {
+            match rx.try_receive() {
+                Some(item) => {
+                    assert_eq!(item, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(rx.try_receive(), None);
+    }
+}
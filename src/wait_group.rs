@@ -0,0 +1,115 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+struct Shared {
+    pending: Mutex<usize>,
+    finished: Condvar,
+}
+
+/// Lets a coordinator block until a number of tasks have finished, like Go's `sync.WaitGroup`.
+/// Clones share the same counter.
+#[derive(Clone)]
+pub struct WaitGroup {
+    shared: Arc<Shared>,
+}
+
+impl WaitGroup {
+    pub fn new() -> Self {
+        WaitGroup {
+            shared: Arc::new(Shared {
+                pending: Mutex::new(0),
+                finished: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Adds `n` tasks to wait for.
+    pub fn add(&self, n: usize) {
+        *self.shared.pending.lock().unwrap() += n;
+    }
+
+    /// Marks one task as finished, waking the waiters once none are pending.
+    ///
+    /// Panics if there is no pending task, i.e. `done` was called more often than `add`.
+    pub fn done(&self) {
+        let mut pending = self.shared.pending.lock().unwrap();
+        *pending = pending
+            .checked_sub(1)
+            .expect("WaitGroup::done called more often than tasks were added");
+        if *pending == 0 {
+            self.shared.finished.notify_all();
+        }
+    }
+
+    /// Blocks until no tasks are pending. Returns right away if none were added.
+    pub fn wait(&self) {
+        let mut pending = self.shared.pending.lock().unwrap();
+        while *pending != 0 {
+            pending = self.shared.finished.wait(pending).unwrap();
+        }
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    #[test]
+    fn test_wait_for_workers() {
+        let wait_group = WaitGroup::new();
+        let finished = Arc::new(AtomicUsize::new(0));
+        wait_group.add(10);
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let wait_group = wait_group.clone();
+                let finished = Arc::clone(&finished);
+                thread::spawn(move || {
+                    thread::sleep(std::time::Duration::from_millis(i * 5));
+                    finished.fetch_add(1, Ordering::Relaxed);
+                    wait_group.done();
+                })
+            })
+            .collect();
+
+        wait_group.wait();
+        assert_eq!(finished.load(Ordering::Relaxed), 10);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_wait_without_tasks_returns_immediately() {
+        WaitGroup::new().wait();
+    }
+
+    #[test]
+    fn test_several_waiters() {
+        let wait_group = WaitGroup::default();
+        wait_group.add(1);
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let wait_group = wait_group.clone();
+                thread::spawn(move || wait_group.wait())
+            })
+            .collect();
+        thread::sleep(std::time::Duration::from_millis(20));
+        wait_group.done();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "more often than tasks were added")]
+    fn test_done_without_add() {
+        WaitGroup::new().done();
+    }
+}
@@ -0,0 +1,64 @@
+//! Checks when `small_channel` allocates. Lives in its own test binary, so the counting
+//! allocator only replaces the global allocator here.
+
+use rust_exploration::small_channel::small_channel;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts allocations per thread, so tests running in parallel don't disturb each other.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The thread-local may already be gone while a thread shuts down.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn test_inline_messages_dont_allocate() {
+    let (mut tx, mut rx) = small_channel::<u64, 4>();
+    let before = allocations();
+    for round in 0..10 {
+        for i in 0..3 {
+            tx.send(round * 3 + i).unwrap();
+        }
+        for i in 0..3 {
+            assert_eq!(rx.receive(), Some(round * 3 + i));
+        }
+    }
+    assert_eq!(allocations(), before);
+}
+
+#[test]
+fn test_spills_past_inline_in_order() {
+    let (mut tx, mut rx) = small_channel::<u64, 4>();
+    let before = allocations();
+    for i in 0..10 {
+        tx.send(i).unwrap();
+    }
+    assert!(allocations() > before);
+
+    // Messages sent while spilled messages remain keep their place behind them.
+    assert_eq!(rx.receive(), Some(0));
+    assert_eq!(rx.receive(), Some(1));
+    tx.send(10).unwrap();
+    drop(tx);
+    assert_eq!(rx.collect::<Vec<_>>(), (2..=10).collect::<Vec<_>>());
+}